@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+
+use super::{
+    parse::{BlockKind, Stmt, StmtKind, Token},
+    Assembly,
+};
+
+/// How deeply macro calls may nest before the expansion is assumed to
+/// never end.
+const MAX_MACRO_DEPTH: usize = 64;
+
+const INDENT: &str = "  ";
+
+/// The built-in instructions and their arguments, which macros may not
+/// shadow.
+const INSTRUCTIONS: [(&str, &str); 10] = [
+    ("set", "CELL VALUE"),
+    ("add", "CELL VALUE"),
+    ("sub", "CELL VALUE"),
+    ("clear", "CELL"),
+    ("move", "FROM TO"),
+    ("copy", "FROM TO"),
+    ("in", "CELL"),
+    ("out", "CELL"),
+    ("print", "\"STRING\""),
+    ("printnum", "CELL"),
+];
+
+/// The words that start or end a statement of their own.
+const KEYWORDS: [&str; 5] = ["cell", "if", "while", "macro", "end"];
+
+struct Macro<'a> {
+    params: &'a [String],
+    body: &'a [Stmt],
+}
+
+struct Compiler<'a> {
+    macros: HashMap<&'a str, Macro<'a>>,
+    cells: HashMap<String, usize>,
+    names: Vec<String>,
+    /// Whether each temporary cell, placed after the named cells, is in use.
+    /// Temporary cells are always 0 when they are not in use.
+    temps: Vec<bool>,
+    /// The cell the pointer is on at this point in the generated code.
+    pos: usize,
+    /// The line of the statement being compiled.
+    line: usize,
+    depth: usize,
+    macro_depth: usize,
+    code: String,
+    line_start: bool,
+    lines: Vec<usize>,
+}
+
+pub fn compile(program: &[Stmt]) -> Result<Assembly> {
+    let mut compiler = Compiler {
+        macros: HashMap::new(),
+        cells: HashMap::new(),
+        names: Vec::new(),
+        temps: Vec::new(),
+        pos: 0,
+        line: 0,
+        depth: 0,
+        macro_depth: 0,
+        code: String::new(),
+        line_start: true,
+        lines: Vec::new(),
+    };
+
+    // Cells and macros are declared up front so that temporary cells never
+    // overlap a named cell and macros may be called before their definition
+    for stmt in program {
+        match &stmt.kind {
+            StmtKind::Cells(names) => {
+                for name in names {
+                    if compiler.cells.contains_key(name) {
+                        bail!(
+                            "line {}: cell `{name}` already exists",
+                            stmt.line
+                        );
+                    }
+                    compiler.cells.insert(name.clone(), compiler.names.len());
+                    compiler.names.push(name.clone());
+                }
+            }
+            StmtKind::Macro { name, params, body } => {
+                let is_builtin = INSTRUCTIONS.iter().any(|(i, _)| i == name)
+                    || KEYWORDS.contains(&name.as_str());
+                if is_builtin {
+                    bail!(
+                        "line {}: macro `{name}` would shadow the built-in \
+                         `{name}`",
+                        stmt.line
+                    );
+                }
+                let def = Macro { params, body };
+                if compiler.macros.insert(name, def).is_some() {
+                    bail!("line {}: macro `{name}` already exists", stmt.line);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for stmt in program {
+        if !matches!(stmt.kind, StmtKind::Cells(_) | StmtKind::Macro { .. }) {
+            compiler.stmt(stmt)?;
+        }
+    }
+
+    if !compiler.line_start {
+        compiler.code.push('\n');
+    }
+
+    Ok(Assembly {
+        code: compiler.code,
+        lines: compiler.lines,
+        cells: compiler.names,
+    })
+}
+
+impl Compiler<'_> {
+    fn stmt(&mut self, stmt: &Stmt) -> Result<()> {
+        self.line = stmt.line;
+        self.new_line();
+
+        match &stmt.kind {
+            StmtKind::Cells(_) => {
+                bail!(
+                    "line {}: cells must be declared at top level",
+                    stmt.line
+                )
+            }
+            StmtKind::Macro { .. } => {
+                bail!(
+                    "line {}: macros must be defined at top level",
+                    stmt.line
+                )
+            }
+            StmtKind::Call { name, args } => self
+                .call(name, args)
+                .with_context(|| format!("line {}", stmt.line)),
+            StmtKind::Block {
+                kind,
+                cell,
+                body,
+                end_line,
+            } => {
+                let cell = self
+                    .cell(cell)
+                    .with_context(|| format!("line {}", stmt.line))?;
+                let counter = match kind {
+                    BlockKind::If => {
+                        let t = self.alloc();
+                        self.copy(cell, t);
+                        self.open(t);
+                        self.clear(t);
+                        t
+                    }
+                    BlockKind::While => {
+                        self.open(cell);
+                        cell
+                    }
+                };
+
+                self.depth += 1;
+                for stmt in body {
+                    self.stmt(stmt)?;
+                }
+                self.depth -= 1;
+
+                self.line = *end_line;
+                self.new_line();
+                self.close(counter);
+                if *kind == BlockKind::If {
+                    self.free(counter);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Token]) -> Result<()> {
+        match (name, args) {
+            ("set", [cell, value]) => {
+                let (cell, value) = (self.cell(cell)?, number(value)?);
+                self.clear(cell);
+                self.add(cell, value);
+            }
+            ("add", [cell, value]) => {
+                let (cell, value) = (self.cell(cell)?, number(value)?);
+                self.add(cell, value);
+            }
+            ("sub", [cell, value]) => {
+                let (cell, value) = (self.cell(cell)?, number(value)?);
+                self.add(cell, -value);
+            }
+            ("clear", [cell]) => {
+                let cell = self.cell(cell)?;
+                self.clear(cell);
+            }
+            ("move", [from, to]) => {
+                let (from, to) = (self.cell(from)?, self.cell(to)?);
+                if from != to {
+                    self.clear(to);
+                    self.transfer(from, &[to]);
+                }
+            }
+            ("copy", [from, to]) => {
+                let (from, to) = (self.cell(from)?, self.cell(to)?);
+                if from != to {
+                    self.clear(to);
+                    self.copy(from, to);
+                }
+            }
+            ("in", [cell]) => {
+                let cell = self.cell(cell)?;
+                self.goto(cell);
+                self.emit(",");
+            }
+            ("out", [cell]) => {
+                let cell = self.cell(cell)?;
+                self.goto(cell);
+                self.emit(".");
+            }
+            ("print", [Token::Str(text)]) => self.print(text),
+            ("printnum", [cell]) => {
+                let cell = self.cell(cell)?;
+                self.print_number(cell);
+            }
+            _ => {
+                if let Some(def) = self.macros.get(name) {
+                    let (params, body) = (def.params, def.body);
+                    return self.expand(name, params, body, args);
+                }
+                match INSTRUCTIONS.iter().find(|(i, _)| *i == name) {
+                    Some((_, usage)) => bail!("usage: {name} {usage}"),
+                    None => bail!("unknown instruction or macro `{name}`"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expand(
+        &mut self,
+        name: &str,
+        params: &[String],
+        body: &[Stmt],
+        args: &[Token],
+    ) -> Result<()> {
+        if args.len() != params.len() {
+            bail!(
+                "macro `{name}` takes {} arguments, found {}",
+                params.len(),
+                args.len()
+            );
+        }
+        if self.macro_depth == MAX_MACRO_DEPTH {
+            bail!("macro `{name}` is nested more than {MAX_MACRO_DEPTH} deep");
+        }
+
+        let call_line = self.line;
+        self.macro_depth += 1;
+        for stmt in body {
+            let stmt = Stmt {
+                line: stmt.line,
+                kind: stmt.kind.substitute(params, args),
+            };
+            self.stmt(&stmt)
+                .with_context(|| format!("in macro `{name}`"))?;
+        }
+        self.macro_depth -= 1;
+        self.line = call_line;
+        Ok(())
+    }
+
+    fn cell(&self, token: &Token) -> Result<usize> {
+        match token {
+            Token::Word(name) => self
+                .cells
+                .get(name)
+                .copied()
+                .with_context(|| format!("unknown cell `{name}`")),
+            _ => bail!("expected a cell, found `{token}`"),
+        }
+    }
+
+    /// Print each digit of the cell, counting up to its value in decimal.
+    fn print_number(&mut self, cell: usize) {
+        let counter = self.alloc();
+        self.copy(cell, counter);
+        let digits = [self.alloc(), self.alloc(), self.alloc()];
+
+        self.open(counter);
+        self.add(counter, -1);
+        self.increment(&digits);
+        self.close(counter);
+        self.free(counter);
+
+        // Leading zeros are skipped until a nonzero digit is found
+        let started = self.alloc();
+        for &digit in digits[1..].iter().rev() {
+            let t = self.alloc();
+            self.copy(digit, t);
+            self.open(t);
+            self.clear(t);
+            self.clear(started);
+            self.add(started, 1);
+            self.close(t);
+
+            self.copy(started, t);
+            self.open(t);
+            self.clear(t);
+            self.print_digit(digit);
+            self.close(t);
+            self.free(t);
+        }
+        self.print_digit(digits[0]);
+
+        self.clear(started);
+        self.free(started);
+        for digit in digits {
+            self.clear(digit);
+            self.free(digit);
+        }
+    }
+
+    fn print_digit(&mut self, digit: usize) {
+        self.add(digit, i64::from(b'0'));
+        self.emit(".");
+        self.add(digit, -i64::from(b'0'));
+    }
+
+    /// Add one to a decimal number held one digit per cell, least
+    /// significant first.
+    fn increment(&mut self, digits: &[usize]) {
+        let (&digit, rest) = digits.split_first().expect("no digits");
+        self.add(digit, 1);
+        if rest.is_empty() {
+            return;
+        }
+
+        // Carry when a copy of the digit minus 10 is 0
+        let carry = self.alloc();
+        self.add(carry, 1);
+        let t = self.alloc();
+        self.copy(digit, t);
+        self.add(t, -10);
+        self.open(t);
+        self.clear(t);
+        self.clear(carry);
+        self.close(t);
+        self.free(t);
+
+        self.open(carry);
+        self.clear(carry);
+        self.clear(digit);
+        self.increment(rest);
+        self.close(carry);
+        self.free(carry);
+    }
+
+    fn print(&mut self, text: &[u8]) {
+        let t = self.alloc();
+        let mut value = 0;
+        for &b in text {
+            self.add(t, i64::from(b) - i64::from(value));
+            self.emit(".");
+            value = b;
+        }
+        self.clear(t);
+        self.free(t);
+    }
+
+    fn alloc(&mut self) -> usize {
+        let i = match self.temps.iter().position(|used| !used) {
+            Some(i) => i,
+            None => {
+                self.temps.push(false);
+                self.temps.len() - 1
+            }
+        };
+        self.temps[i] = true;
+        self.names.len() + i
+    }
+
+    /// Release a temporary cell. It must already be 0.
+    fn free(&mut self, cell: usize) {
+        self.temps[cell - self.names.len()] = false;
+    }
+
+    fn goto(&mut self, cell: usize) {
+        let code = if cell > self.pos { ">" } else { "<" };
+        let n = cell.abs_diff(self.pos);
+        self.emit(&code.repeat(n));
+        self.pos = cell;
+    }
+
+    fn add(&mut self, cell: usize, value: i64) {
+        let value = value.rem_euclid(256);
+        self.goto(cell);
+        if value <= 128 {
+            self.emit(&"+".repeat(value as usize));
+        } else {
+            self.emit(&"-".repeat(256 - value as usize));
+        }
+    }
+
+    fn clear(&mut self, cell: usize) {
+        self.goto(cell);
+        self.emit("[-]");
+    }
+
+    fn open(&mut self, cell: usize) {
+        self.goto(cell);
+        self.emit("[");
+    }
+
+    fn close(&mut self, cell: usize) {
+        self.goto(cell);
+        self.emit("]");
+    }
+
+    /// Add the value of `from` to each of `to`, leaving `from` 0.
+    fn transfer(&mut self, from: usize, to: &[usize]) {
+        self.open(from);
+        self.add(from, -1);
+        for &cell in to {
+            self.add(cell, 1);
+        }
+        self.close(from);
+    }
+
+    /// Add the value of `from` to `to`, through a temporary cell.
+    fn copy(&mut self, from: usize, to: usize) {
+        let t = self.alloc();
+        self.transfer(from, &[to, t]);
+        self.transfer(t, &[from]);
+        self.free(t);
+    }
+
+    /// Start the code of the next statement on its own line.
+    fn new_line(&mut self) {
+        if !self.line_start {
+            self.code.push('\n');
+            self.line_start = true;
+        }
+    }
+
+    fn emit(&mut self, code: &str) {
+        if code.is_empty() {
+            return;
+        }
+        if self.line_start {
+            self.code.push_str(&INDENT.repeat(self.depth));
+            self.line_start = false;
+        }
+        self.code.push_str(code);
+        self.lines.extend(code.chars().map(|_| self.line));
+    }
+}
+
+fn number(token: &Token) -> Result<i64> {
+    match token {
+        Token::Number(value) if (-255..=255).contains(value) => Ok(*value),
+        Token::Number(_) => bail!("`{token}` is out of range -255 to 255"),
+        _ => bail!("expected a number, found `{token}`"),
+    }
+}
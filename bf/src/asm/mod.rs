@@ -0,0 +1,52 @@
+//! A small assembly language that compiles to Brainfuck.
+//!
+//! Each line holds one statement, and `;` or `#` starts a comment.
+//!
+//! ```text
+//! cell a, b           ; name cells, in order from cell 0
+//! set a 'A'           ; set a cell to a number or a character
+//! add a 2             ; add to or `sub` from a cell, wrapping at 256
+//! clear a             ; set a cell to 0
+//! move a b            ; set b to a and a to 0
+//! copy a b            ; set b to a
+//! in a                ; read a byte into a cell
+//! out a               ; print a cell as a byte
+//! print "hi\n"        ; print a string
+//! printnum a          ; print a cell as a decimal number
+//! if a ... end        ; run the body once if a is not 0
+//! while a ... end     ; run the body as long as a is not 0
+//! macro twice x       ; define a macro with parameters
+//!     out x           ; parameters are replaced with the arguments
+//!     out x
+//! end
+//! twice a             ; call a macro
+//! ```
+//!
+//! Temporary cells needed by the statements are allocated after the named
+//! cells and are always 0 when they are not in use.
+
+use anyhow::Result;
+
+mod compile;
+
+mod parse;
+
+#[cfg(test)]
+mod test;
+
+/// The result of assembling a program.
+#[derive(Debug)]
+pub struct Assembly {
+    /// The Brainfuck code, with the code of each statement on its own line.
+    pub code: String,
+    /// The 1-based line of the assembly that produced each instruction of
+    /// the code.
+    pub lines: Vec<usize>,
+    /// The names of the named cells, by their index on the tape.
+    pub cells: Vec<String>,
+}
+
+/// Compile assembly source to Brainfuck.
+pub fn assemble(source: &str) -> Result<Assembly> {
+    compile::compile(&parse::parse(source)?)
+}
@@ -0,0 +1,318 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+use anyhow::{bail, Context, Result};
+
+/// One argument of a statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    /// A cell, macro, or parameter name.
+    Word(String),
+    /// A number or a character literal such as `'a'`.
+    Number(i64),
+    /// A string literal, after escape sequences are replaced.
+    Str(Vec<u8>),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(word) => f.write_str(word),
+            Self::Number(value) => write!(f, "{value}"),
+            Self::Str(bytes) => {
+                write!(f, "{:?}", String::from_utf8_lossy(bytes))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockKind {
+    If,
+    While,
+}
+
+#[derive(Clone, Debug)]
+pub struct Stmt {
+    /// The 1-based line of the statement in the source.
+    pub line: usize,
+    pub kind: StmtKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum StmtKind {
+    /// `cell NAME...`
+    Cells(Vec<String>),
+    /// A built-in instruction or a macro call.
+    Call { name: String, args: Vec<Token> },
+    /// `if CELL` or `while CELL`, up to the matching `end`.
+    Block {
+        kind: BlockKind,
+        cell: Token,
+        body: Vec<Stmt>,
+        end_line: usize,
+    },
+    /// `macro NAME PARAMS...`, up to the matching `end`.
+    Macro {
+        name: String,
+        params: Vec<String>,
+        body: Vec<Stmt>,
+    },
+}
+
+impl StmtKind {
+    /// Replace each parameter in the statement with its argument.
+    pub fn substitute(&self, params: &[String], args: &[Token]) -> Self {
+        let sub = |token: &Token| match token {
+            Token::Word(word) => params
+                .iter()
+                .position(|p| p == word)
+                .map_or_else(|| token.clone(), |i| args[i].clone()),
+            _ => token.clone(),
+        };
+        match self {
+            Self::Call { name, args } => Self::Call {
+                name: name.clone(),
+                args: args.iter().map(sub).collect(),
+            },
+            Self::Block {
+                kind,
+                cell,
+                body,
+                end_line,
+            } => Self::Block {
+                kind: *kind,
+                cell: sub(cell),
+                body: body
+                    .iter()
+                    .map(|stmt| Stmt {
+                        line: stmt.line,
+                        kind: stmt.kind.substitute(params, args),
+                    })
+                    .collect(),
+                end_line: *end_line,
+            },
+            Self::Cells(_) | Self::Macro { .. } => self.clone(),
+        }
+    }
+}
+
+/// A block that has been opened but not yet closed by `end`.
+struct Open {
+    line: usize,
+    kind: OpenKind,
+    body: Vec<Stmt>,
+}
+
+enum OpenKind {
+    Block(BlockKind, Token),
+    Macro(String, Vec<String>),
+}
+
+pub fn parse(source: &str) -> Result<Vec<Stmt>> {
+    let mut top = Vec::new();
+    let mut open: Vec<Open> = Vec::new();
+
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let tokens = tokenize(text).with_context(|| format!("line {line}"))?;
+        let mut tokens = tokens.into_iter();
+        let Some(first) = tokens.next() else {
+            continue;
+        };
+        let Token::Word(word) = first else {
+            bail!("line {line}: expected an instruction, found `{first}`");
+        };
+        let args = tokens.collect::<Vec<_>>();
+
+        let stmt = match word.as_str() {
+            "if" | "while" => {
+                let kind = if word == "if" {
+                    BlockKind::If
+                } else {
+                    BlockKind::While
+                };
+                let [cell] = <[Token; 1]>::try_from(args).map_err(|_| {
+                    anyhow::anyhow!("line {line}: {word} takes one cell")
+                })?;
+                open.push(Open {
+                    line,
+                    kind: OpenKind::Block(kind, cell),
+                    body: Vec::new(),
+                });
+                continue;
+            }
+            "macro" => {
+                if !open.is_empty() {
+                    bail!("line {line}: macros must be defined at top level");
+                }
+                let mut names = args.into_iter().map(|arg| match arg {
+                    Token::Word(name) => Ok(name),
+                    _ => bail!("line {line}: expected a name, found `{arg}`"),
+                });
+                let Some(name) = names.next() else {
+                    bail!("line {line}: macro requires a name");
+                };
+                open.push(Open {
+                    line,
+                    kind: OpenKind::Macro(
+                        name?,
+                        names.collect::<Result<_>>()?,
+                    ),
+                    body: Vec::new(),
+                });
+                continue;
+            }
+            "end" => {
+                if !args.is_empty() {
+                    bail!("line {line}: end takes no arguments");
+                }
+                let Some(block) = open.pop() else {
+                    bail!("line {line}: end without an if, while or macro");
+                };
+                let kind = match block.kind {
+                    OpenKind::Block(kind, cell) => StmtKind::Block {
+                        kind,
+                        cell,
+                        body: block.body,
+                        end_line: line,
+                    },
+                    OpenKind::Macro(name, params) => StmtKind::Macro {
+                        name,
+                        params,
+                        body: block.body,
+                    },
+                };
+                Stmt {
+                    line: block.line,
+                    kind,
+                }
+            }
+            "cell" => {
+                let names = args
+                    .into_iter()
+                    .map(|arg| match arg {
+                        Token::Word(name) => Ok(name),
+                        _ => bail!(
+                            "line {line}: expected a cell name, found `{arg}`"
+                        ),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if names.is_empty() {
+                    bail!("line {line}: cell requires at least one name");
+                }
+                Stmt {
+                    line,
+                    kind: StmtKind::Cells(names),
+                }
+            }
+            _ => Stmt {
+                line,
+                kind: StmtKind::Call { name: word, args },
+            },
+        };
+
+        match open.last_mut() {
+            Some(block) => block.body.push(stmt),
+            None => top.push(stmt),
+        }
+    }
+
+    if let Some(block) = open.last() {
+        bail!("line {}: missing end", block.line);
+    }
+
+    Ok(top)
+}
+
+/// Split a line into tokens. Arguments are separated by whitespace or
+/// commas, and a `;` or `#` outside of a literal starts a comment.
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            ';' | '#' => break,
+            c if c.is_whitespace() || c == ',' => {
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let mut bytes = Vec::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => bytes.push(escape(&mut chars)?),
+                        Some(c) => {
+                            let mut buf = [0; 4];
+                            bytes.extend(c.encode_utf8(&mut buf).bytes());
+                        }
+                        None => bail!("unterminated string"),
+                    }
+                }
+                tokens.push(Token::Str(bytes));
+            }
+            '\'' => {
+                chars.next();
+                let value = match chars.next() {
+                    Some('\\') => escape(&mut chars)?,
+                    Some(c) if c.is_ascii() => c as u8,
+                    _ => bail!("invalid character literal"),
+                };
+                if chars.next() != Some('\'') {
+                    bail!("unterminated character literal");
+                }
+                tokens.push(Token::Number(value.into()));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) =
+                    chars.peek().filter(|c| !is_separator(**c))
+                {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(parse_word(word)?);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+const fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | ';' | '#' | '"' | '\'')
+}
+
+fn parse_word(word: String) -> Result<Token> {
+    let digits = word.trim_start_matches(['-', '+']);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(Token::Word(word));
+    }
+
+    let negative = word.starts_with('-');
+    let value = match digits.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16),
+        None => digits.parse(),
+    }
+    .with_context(|| format!("invalid number: {word}"))?;
+    Ok(Token::Number(if negative { -value } else { value }))
+}
+
+fn escape(chars: &mut Peekable<Chars>) -> Result<u8> {
+    Ok(match chars.next() {
+        Some('n') => b'\n',
+        Some('r') => b'\r',
+        Some('t') => b'\t',
+        Some('0') => b'\0',
+        Some(c @ ('\\' | '"' | '\'')) => c as u8,
+        Some('x') => {
+            let hex = chars.take(2).collect::<String>();
+            u8::from_str_radix(&hex, 16).with_context(|| {
+                format!("invalid escape sequence: \\x{hex}")
+            })?
+        }
+        Some(c) => bail!("invalid escape sequence: \\{c}"),
+        None => bail!("incomplete escape sequence"),
+    })
+}
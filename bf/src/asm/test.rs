@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+
+use proptest::prelude::*;
+
+use super::assemble;
+use crate::interpreter::{Interpreter, Tape};
+
+fn run(source: &str, input: &[u8]) -> (Vec<u8>, Tape) {
+    let asm = assemble(source).expect("failed to assemble");
+    assert_eq!(
+        asm.lines.len(),
+        asm.code
+            .bytes()
+            .filter(|b| Interpreter::is_instruction(*b))
+            .count()
+    );
+    let mut int = Interpreter::new(
+        asm.code.bytes(),
+        input.iter().copied().collect::<VecDeque<_>>(),
+        Some(0),
+    );
+    for frame in int.by_ref() {
+        frame.expect("assembled program errored");
+    }
+    (int.output, int.tape)
+}
+
+fn error(source: &str) -> String {
+    format!("{:#}", assemble(source).unwrap_err())
+}
+
+#[test]
+fn cells_and_data_movement() {
+    let source = "
+        cell a, b, c
+        set a 'x'
+        copy a b
+        add b 2
+        move b c
+        sub a 300 ; out of range
+    ";
+    assert!(error(source).contains("out of range"));
+
+    let source = source.replace("sub a 300", "sub a 1");
+    let (_, tape) = run(&source, b"");
+    let values = tape.values().collect::<Vec<_>>();
+    assert_eq!(values[..3], [b'w', 0, b'z']);
+    // Every temporary cell is left 0
+    assert!(values[3..].iter().all(|v| *v == 0));
+}
+
+#[test]
+fn blocks_and_macros() {
+    let source = "
+        cell n, c
+        macro shout x
+            out x
+            print \"!\\n\"
+        end
+        in n
+        while n
+            copy n c
+            if c
+                shout n
+            end
+            in n
+        end
+    ";
+    let (output, _) = run(source, b"ab");
+    assert_eq!(output, b"a!\nb!\n");
+}
+
+#[test]
+fn source_map_points_at_statements() {
+    let asm = assemble("cell a\nset a 3\n\nprint \"x\"\n").unwrap();
+    assert_eq!(asm.cells, ["a"]);
+    assert_eq!(asm.code.lines().count(), 2);
+    assert_eq!(asm.lines.first(), Some(&2));
+    assert_eq!(asm.lines.last(), Some(&4));
+    assert!(asm.lines.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn errors_name_the_line() {
+    assert_eq!(error("cell a\nset b 1"), "line 2: unknown cell `b`");
+    assert_eq!(error("cell a\nif a\n"), "line 2: missing end");
+    assert_eq!(error("end"), "line 1: end without an if, while or macro");
+    assert_eq!(
+        error("frob 1"),
+        "line 1: unknown instruction or macro `frob`"
+    );
+    assert_eq!(error("cell a\nset a"), "line 2: usage: set CELL VALUE");
+    assert_eq!(error("cell a\ncell a"), "line 2: cell `a` already exists");
+    assert!(error("macro m\nm\nend\nm").contains("nested more than"));
+    assert!(error("print \"abc").contains("unterminated string"));
+    assert_eq!(
+        error("macro out x\nend"),
+        "line 1: macro `out` would shadow the built-in `out`"
+    );
+    assert!(error("cell x\nmacro if x\nend").contains("shadow"));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn printnum_prints_decimal(value in any::<u8>()) {
+        let source = format!("cell a\nset a {value}\nprintnum a\nprintnum a");
+        let (output, _) = run(&source, b"");
+        prop_assert_eq!(output, format!("{value}{value}").into_bytes());
+    }
+}
@@ -0,0 +1,218 @@
+//! Translation between Brainfuck and dialects that replace each instruction
+//! with a different token, such as Ook!.
+
+use std::iter;
+
+use anyhow::{bail, Result};
+
+/// The Brainfuck instructions, in the order of the tokens of a [`Dialect`].
+pub const INSTRUCTIONS: [u8; 8] = *b"><+-.,[]";
+
+/// The names of the built-in dialects.
+pub const DIALECTS: [&str; 4] = ["brainfuck", "ook", "blub", "alphuck"];
+
+/// A dialect of Brainfuck that has a token for each instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dialect {
+    tokens: [Vec<u8>; 8],
+    /// Put between consecutive tokens on a line when encoding.
+    separator: String,
+}
+
+impl Dialect {
+    /// Create a dialect from its tokens, in the order of [`INSTRUCTIONS`].
+    ///
+    /// A space in a token matches any amount of whitespace, including line
+    /// breaks, when decoding.
+    pub fn new(tokens: [String; 8], separator: String) -> Result<Self> {
+        let tokens = tokens.map(|token| {
+            token.split_whitespace().collect::<Vec<_>>().join(" ")
+        });
+        for (i, token) in tokens.iter().enumerate() {
+            let instruction = char::from(INSTRUCTIONS[i]);
+            if token.is_empty() {
+                bail!("the token for `{instruction}` is empty");
+            }
+            if tokens[..i].contains(token) {
+                bail!("the token for `{instruction}` is used more than once");
+            }
+        }
+        Ok(Self {
+            tokens: tokens.map(String::into_bytes),
+            separator,
+        })
+    }
+
+    /// Get a built-in dialect by name.
+    pub fn named(name: &str) -> Option<Self> {
+        let (tokens, separator) = match name {
+            "brainfuck" => (INSTRUCTIONS.map(|b| char::from(b).into()), ""),
+            "ook" => (ook_tokens("Ook"), " "),
+            "blub" => (ook_tokens("Blub"), " "),
+            "alphuck" => (b"aceijops".map(|b| char::from(b).into()), ""),
+            _ => return None,
+        };
+        Self::new(tokens, separator.into()).ok()
+    }
+
+    /// Translate a source in this dialect to Brainfuck. Anything that is not
+    /// a token is dropped, except for line breaks, which are kept so that
+    /// the lines of the source stay the same.
+    pub fn decode(&self, source: &[u8]) -> Vec<u8> {
+        let newlines =
+            |text: &[u8]| text.iter().filter(|b| **b == b'\n').count();
+        let mut code = Vec::with_capacity(source.len());
+        let mut end = 0;
+
+        for (start, len, instruction) in self.tokens(source) {
+            code.extend(iter::repeat_n(b'\n', newlines(&source[end..start])));
+            // A token that spans lines is put on the line where it starts
+            code.push(instruction);
+            end = start + len;
+            code.extend(iter::repeat_n(b'\n', newlines(&source[start..end])));
+        }
+        code.extend(iter::repeat_n(b'\n', newlines(&source[end..])));
+
+        code
+    }
+
+    /// The offset in `source` of the token of each instruction given by
+    /// [`Self::decode`], to map the instructions back to the source.
+    pub fn token_offsets<'a>(
+        &'a self,
+        source: &'a [u8],
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.tokens(source).map(|(start, _, _)| start)
+    }
+
+    /// The offset, length and instruction of each token in `source`.
+    fn tokens<'a>(
+        &'a self,
+        source: &'a [u8],
+    ) -> impl Iterator<Item = (usize, usize, u8)> + 'a {
+        let mut i = 0;
+        iter::from_fn(move || {
+            while i < source.len() {
+                let found =
+                    self.tokens.iter().enumerate().find_map(|(t, token)| {
+                        match_token(token, &source[i..]).map(|len| (t, len))
+                    });
+                let start = i;
+                match found {
+                    Some((t, len)) => {
+                        i += len;
+                        return Some((start, len, INSTRUCTIONS[t]));
+                    }
+                    None => i += 1,
+                }
+            }
+            None
+        })
+    }
+
+    /// Translate Brainfuck to this dialect. Comments are dropped and line
+    /// breaks are kept.
+    pub fn encode(&self, code: &[u8]) -> Vec<u8> {
+        let mut source = Vec::with_capacity(code.len());
+        let mut line_start = true;
+
+        for &b in code {
+            if b == b'\n' {
+                source.push(b'\n');
+                line_start = true;
+            } else if let Some(t) = INSTRUCTIONS.iter().position(|i| *i == b) {
+                if !line_start {
+                    source.extend_from_slice(self.separator.as_bytes());
+                }
+                source.extend_from_slice(&self.tokens[t]);
+                line_start = false;
+            }
+        }
+
+        source
+    }
+}
+
+fn ook_tokens(word: &str) -> [String; 8] {
+    [
+        ('.', '?'),
+        ('?', '.'),
+        ('.', '.'),
+        ('!', '!'),
+        ('!', '.'),
+        ('.', '!'),
+        ('!', '?'),
+        ('?', '!'),
+    ]
+    .map(|(a, b)| format!("{word}{a} {word}{b}"))
+}
+
+/// The length of the token at the start of the source, if there is one.
+fn match_token(token: &[u8], source: &[u8]) -> Option<usize> {
+    let mut len = 0;
+    for (i, part) in token.split(|b| *b == b' ').enumerate() {
+        if i > 0 {
+            let spaces = source[len..]
+                .iter()
+                .take_while(|b| b.is_ascii_whitespace())
+                .count();
+            if spaces == 0 {
+                return None;
+            }
+            len += spaces;
+        }
+        if !source[len..].starts_with(part) {
+            return None;
+        }
+        len += part.len();
+    }
+    Some(len)
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::{Dialect, DIALECTS};
+
+    #[test]
+    fn ook() {
+        let ook = Dialect::named("ook").unwrap();
+        assert_eq!(
+            ook.encode(b"+[-]  x\n."),
+            b"Ook. Ook. Ook! Ook? Ook! Ook! Ook? Ook!\nOok! Ook."
+        );
+        assert_eq!(
+            ook.decode(b"Ook. Ook?\n Ook. Ook. Ook!\nOok."),
+            b">\n+.\n"
+        );
+        let offsets = ook.token_offsets(b"Ook. Ook?\n Ook. Ook. Ook!\nOok.");
+        assert_eq!(offsets.collect::<Vec<_>>(), [0, 11, 21]);
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        let tokens = |last: &str| {
+            ["a", "b", "c", "d", "e", "f", "g", last].map(String::from)
+        };
+        assert!(Dialect::new(tokens("h"), String::new()).is_ok());
+        assert!(Dialect::new(tokens("a"), String::new()).is_err());
+        assert!(Dialect::new(tokens(" "), String::new()).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn encode_then_decode(code in "[><+\\-.,\\[\\]\n]{0,64}") {
+            for name in DIALECTS {
+                let dialect = Dialect::named(name).unwrap();
+                let source = dialect.encode(code.as_bytes());
+                prop_assert_eq!(
+                    dialect.decode(&source),
+                    code.as_bytes(),
+                    "dialect: {}",
+                    name
+                );
+            }
+        }
+    }
+}
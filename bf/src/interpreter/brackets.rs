@@ -0,0 +1,87 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// The brackets of a script matched up by their position, which may be an
+/// index into the instructions or a place in the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Brackets<P: Eq + Hash> {
+    /// Each bracket mapped to its partner, both ways.
+    pub pairs: HashMap<P, P>,
+    /// The brackets without a partner, in the order they appear.
+    pub unmatched: Vec<P>,
+}
+
+impl<P: Copy + Eq + Hash + Ord> Brackets<P> {
+    /// Match each `open` to the nearest following `close` that is not
+    /// already matched.
+    pub fn resolve(
+        code: impl IntoIterator<Item = (P, u8)>,
+        open: u8,
+        close: u8,
+    ) -> Self {
+        let mut open_brackets = Vec::new();
+        let mut pairs = HashMap::new();
+        let mut unmatched = Vec::new();
+
+        for (pos, b) in code {
+            if b == open {
+                open_brackets.push(pos);
+            } else if b == close {
+                match open_brackets.pop() {
+                    Some(open_pos) => {
+                        pairs.insert(open_pos, pos);
+                        pairs.insert(pos, open_pos);
+                    }
+                    None => unmatched.push(pos),
+                }
+            }
+        }
+
+        unmatched.extend(open_brackets);
+        unmatched.sort_unstable();
+        Self { pairs, unmatched }
+    }
+
+    pub fn partner(&self, pos: P) -> Option<P> {
+        self.pairs.get(&pos).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Brackets;
+
+    fn resolve(code: &str) -> Brackets<usize> {
+        Brackets::resolve(code.bytes().enumerate(), b'[', b']')
+    }
+
+    #[test]
+    fn pairs() {
+        let brackets = resolve("[[]x[]]");
+        assert_eq!(brackets.partner(0), Some(6));
+        assert_eq!(brackets.partner(6), Some(0));
+        assert_eq!(brackets.partner(1), Some(2));
+        assert_eq!(brackets.partner(4), Some(5));
+        assert_eq!(brackets.partner(3), None);
+        assert!(brackets.unmatched.is_empty());
+    }
+
+    #[test]
+    fn unmatched() {
+        assert_eq!(resolve("][[]").unmatched, [0, 1]);
+        assert_eq!(resolve("[[]").unmatched, [0]);
+        assert_eq!(resolve("[]][").unmatched, [2, 3]);
+    }
+
+    #[test]
+    fn source_positions() {
+        let lines = ["+[", "-]]"];
+        let code = lines.iter().enumerate().flat_map(|(row, line)| {
+            line.bytes()
+                .enumerate()
+                .map(move |(col, b)| ((row, col), b))
+        });
+        let brackets = Brackets::resolve(code, b'[', b']');
+        assert_eq!(brackets.partner((0, 1)), Some((1, 1)));
+        assert_eq!(brackets.unmatched, [(1, 2)]);
+    }
+}
@@ -0,0 +1,36 @@
+use std::fmt::{self, Display, Formatter};
+
+/// The kinds of error that stop the execution of a script.
+///
+/// Errors returned by the [`Interpreter`](super::Interpreter) can be
+/// downcast to this type to tell them apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A bracket was executed that has no matching bracket.
+    MismatchedBrackets,
+    /// The read (,) instruction could not read a byte from stdin.
+    ReadInput,
+    /// A pbrain procedure was called that has not been defined.
+    UndefinedProcedure(u8),
+    /// pbrain procedure calls nested too deeply.
+    CallStackOverflow,
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MismatchedBrackets => f.write_str("mismatched brackets"),
+            Self::ReadInput => {
+                f.write_str("failed to read character from stdin")
+            }
+            Self::UndefinedProcedure(number) => {
+                write!(f, "procedure {number} is not defined")
+            }
+            Self::CallStackOverflow => {
+                f.write_str("procedure calls are nested too deeply")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ErrorKind {}
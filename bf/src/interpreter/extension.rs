@@ -0,0 +1,252 @@
+use super::Interpreter;
+
+/// An opt-in extension to the Brainfuck instruction set, see
+/// [`Interpreter::with_extension`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Extension {
+    /// Plain Brainfuck.
+    #[default]
+    None,
+    /// pbrain procedures: `(` and `)` define a procedure numbered by the
+    /// current cell, and `:` calls the procedure numbered by the current
+    /// cell.
+    Pbrain,
+    /// Brainfork threads: `Y` forks the current thread. The parent's cell
+    /// is set to 0, and the child starts on the next cell, set to 1.
+    Brainfork,
+    /// Boolfuck: cells are single bits, `+` flips the current bit, `,` reads
+    /// a bit and `;` writes a bit. Bytes are read and written least
+    /// significant bit first. There is no `-` or `.`.
+    Boolfuck,
+}
+
+impl Extension {
+    pub const fn is_instruction(self, byte: u8) -> bool {
+        match self {
+            Self::None => Interpreter::is_instruction(byte),
+            Self::Pbrain => {
+                Interpreter::is_instruction(byte)
+                    || matches!(byte, b'(' | b')' | b':')
+            }
+            Self::Brainfork => {
+                Interpreter::is_instruction(byte) || byte == b'Y'
+            }
+            Self::Boolfuck => {
+                matches!(byte, b'+' | b'<' | b'>' | b'[' | b']' | b',' | b';')
+            }
+        }
+    }
+}
+
+/// How the threads of a Brainfork program take turns, see
+/// [`Interpreter::set_schedule`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Schedule {
+    /// Each thread executes one instruction in turn, so every run of a
+    /// program is the same.
+    #[default]
+    RoundRobin,
+    /// Each thread executes between 1 and [`MAX_SLICE`] instructions in
+    /// turn, picked by a pseudo-random generator started from the seed.
+    Random(u64),
+}
+
+/// The most instructions a thread executes in one turn when the schedule is
+/// random.
+pub const MAX_SLICE: usize = 16;
+
+impl Schedule {
+    /// The number of instructions the next thread executes, and the next
+    /// state of the schedule.
+    pub(super) const fn next_slice(self) -> (usize, Self) {
+        match self {
+            Self::RoundRobin => (1, self),
+            Self::Random(seed) => {
+                // xorshift64, which must never be given a state of 0
+                let mut x = if seed == 0 {
+                    0x9e37_79b9_7f4a_7c15
+                } else {
+                    seed
+                };
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                ((x % MAX_SLICE as u64) as usize + 1, Self::Random(x))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use anyhow::Result;
+
+    use super::{Extension, Schedule};
+    use crate::interpreter::{ErrorKind, Interpreter};
+
+    fn run(code: &str, extension: Extension) -> Result<Vec<u8>> {
+        run_with(code, extension, Schedule::RoundRobin)
+    }
+
+    fn run_with(
+        code: &str,
+        extension: Extension,
+        schedule: Schedule,
+    ) -> Result<Vec<u8>> {
+        let mut int = Interpreter::with_extension(
+            code.bytes(),
+            VecDeque::new(),
+            Some(0),
+            extension,
+        );
+        int.set_schedule(schedule);
+        for frame in int.by_ref() {
+            frame?;
+        }
+        Ok(int.output)
+    }
+
+    fn error(code: &str) -> ErrorKind {
+        let err = run(code, Extension::Pbrain).unwrap_err();
+        *err.downcast_ref::<ErrorKind>().unwrap()
+    }
+
+    #[test]
+    fn pbrain_procedures() {
+        // Procedure 0 prints the current cell, procedure 1 adds 2 to it
+        let code = "(.)+(++):.[-]:";
+        assert_eq!(run(code, Extension::Pbrain).unwrap(), [3, 0]);
+        // Procedures only run when called, and the body is skipped
+        assert_eq!(run("+(.)", Extension::Pbrain).unwrap(), []);
+        // Nested calls return to the right place
+        let code = "+(.)++(--:++):.";
+        assert_eq!(run(code, Extension::Pbrain).unwrap(), [1, 3]);
+    }
+
+    #[test]
+    fn pbrain_is_opt_in() {
+        assert_eq!(run("(+.):", Extension::None).unwrap(), [1]);
+    }
+
+    #[test]
+    fn pbrain_errors() {
+        assert_eq!(error("+++:"), ErrorKind::UndefinedProcedure(3));
+        assert_eq!(error("(.:"), ErrorKind::MismatchedBrackets);
+        assert_eq!(error(".)"), ErrorKind::MismatchedBrackets);
+        assert_eq!(error("(:):"), ErrorKind::CallStackOverflow);
+    }
+
+    #[test]
+    fn brainfork_fork() {
+        // The child prints 1 from the next cell, and runs first since the
+        // parent's turn ends at the fork
+        assert_eq!(run("+Y.", Extension::Brainfork).unwrap(), [1, 0]);
+        // Each thread runs one instruction in turn
+        assert_eq!(run("Y+.", Extension::Brainfork).unwrap(), [2, 1]);
+    }
+
+    #[test]
+    fn brainfork_threads_share_the_tape() {
+        // The child adds 3 to cell 0 before the parent prints it, then
+        // prints its own cell
+        let code = "Y[-<+++>]>>>><<<<.";
+        assert_eq!(run(code, Extension::Brainfork).unwrap(), [3, 0]);
+    }
+
+    #[test]
+    fn brainfork_ip_is_the_next_instruction() {
+        // The parent finishes first while the child is still looping
+        let code = "Y[+++[-]]";
+        for schedule in [Schedule::RoundRobin, Schedule::Random(3)] {
+            let mut int = Interpreter::with_extension(
+                code.bytes(),
+                VecDeque::new(),
+                Some(0),
+                Extension::Brainfork,
+            );
+            int.set_schedule(schedule);
+            loop {
+                let expected = int.peek();
+                let Some(frame) = int.next() else {
+                    break;
+                };
+                assert_eq!(Some(frame.unwrap()), expected, "{schedule:?}");
+            }
+            assert_eq!(int.peek(), None);
+        }
+    }
+
+    #[test]
+    fn brainfork_is_opt_in() {
+        assert_eq!(run("+Y.", Extension::None).unwrap(), [1]);
+    }
+
+    #[test]
+    fn brainfork_schedule() {
+        // Every schedule gives the same output when threads do not race
+        let code = "Y[>++++++++[<++++++>-]<.[-]]";
+        for seed in 0..32 {
+            let schedule = Schedule::Random(seed);
+            let output = run_with(code, Extension::Brainfork, schedule);
+            assert_eq!(output.unwrap(), b"1", "seed: {seed}");
+        }
+        let schedule = Schedule::Random(7);
+        let mut state = schedule;
+        for _ in 0..256 {
+            let (slice, next) = state.next_slice();
+            assert!((1..=super::MAX_SLICE).contains(&slice));
+            state = next;
+        }
+        assert_ne!(state, schedule);
+    }
+
+    #[test]
+    fn boolfuck_bits() {
+        // 'A' is 0b01000001, written least significant bit first
+        let code = "+;+;;;;;+;+;";
+        assert_eq!(run(code, Extension::Boolfuck).unwrap(), b"A");
+        // A partly written byte is padded with zeros
+        assert_eq!(run("+;>++;;", Extension::Boolfuck).unwrap(), [1]);
+        // `-` and `.` are comments
+        assert_eq!(run("+-;.", Extension::Boolfuck).unwrap(), [1]);
+    }
+
+    #[test]
+    fn boolfuck_echo() {
+        let code = ",;".repeat(16);
+        let mut int = Interpreter::with_extension(
+            code.bytes(),
+            b"hi".iter().copied().collect(),
+            None,
+            Extension::Boolfuck,
+        );
+        for frame in int.by_ref() {
+            frame.unwrap();
+        }
+        assert_eq!(int.output, b"hi");
+        assert_eq!(int.bytes_read(), 2);
+        assert_eq!(int.tape.len(), 1);
+    }
+
+    #[test]
+    fn boolfuck_needs_input() {
+        let code = ",".repeat(9);
+        let mut int = Interpreter::with_extension(
+            code.bytes(),
+            b"h".iter().copied().collect(),
+            None,
+            Extension::Boolfuck,
+        );
+        assert!(!int.needs_input());
+        int.next().unwrap().unwrap();
+        // The rest of the byte is still buffered
+        assert!(int.input.is_empty());
+        for _ in 0..7 {
+            assert!(!int.needs_input());
+            int.next().unwrap().unwrap();
+        }
+        assert!(int.needs_input());
+    }
+}
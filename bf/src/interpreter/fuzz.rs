@@ -0,0 +1,218 @@
+//! Differential fuzzing of the execution engines.
+//!
+//! Every engine is run over the same randomly generated, bracket-balanced
+//! programs for a bounded number of steps and must end up in the same state.
+//! New engines only need a `snapshot_*` function added to [`ENGINES`].
+
+use std::collections::VecDeque;
+
+use proptest::prelude::*;
+
+use super::{ErrorKind, Extension, Interpreter};
+
+/// The maximum number of instructions executed by each engine per program.
+const MAX_STEPS: usize = 10_000;
+
+/// The value read by `,` once the generated input runs out.
+const AUTO_INPUT: u8 = 0;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Snapshot {
+    output: Vec<u8>,
+    cells: Vec<u8>,
+    cursor: usize,
+    finished: bool,
+}
+
+type Engine = fn(&[u8], &[u8]) -> Snapshot;
+
+const ENGINES: &[(&str, Engine)] = &[("interpreter", snapshot_interpreter)];
+
+fn snapshot_interpreter(code: &[u8], input: &[u8]) -> Snapshot {
+    let mut int = Interpreter::new(
+        code.iter().copied(),
+        input.iter().copied().collect(),
+        Some(AUTO_INPUT),
+    );
+
+    let mut steps = 0;
+    let finished = loop {
+        if steps == MAX_STEPS {
+            break false;
+        }
+        match int.next() {
+            Some(Ok(_)) => steps += 1,
+            Some(Err(err)) => panic!("balanced program errored: {err}"),
+            None => break true,
+        }
+    };
+
+    Snapshot {
+        output: int.output,
+        cells: int.tape.values().collect(),
+        cursor: int.tape.cursor(),
+        finished,
+    }
+}
+
+/// A deliberately naive engine that re-scans for matching brackets on every
+/// jump. It serves as the oracle the other engines are compared against.
+fn snapshot_reference(code: &[u8], input: &[u8]) -> Snapshot {
+    let code = code
+        .iter()
+        .copied()
+        .filter(|c| b"+-<>[].,".contains(c))
+        .collect::<Vec<_>>();
+    let mut input = input.iter().copied().collect::<VecDeque<_>>();
+    let mut cells = vec![0_u8];
+    let mut cursor = 0_usize;
+    let mut output = Vec::new();
+    let mut ip = 0_usize;
+
+    let mut steps = 0;
+    while ip < code.len() && steps < MAX_STEPS {
+        match code[ip] {
+            b'+' => cells[cursor] = cells[cursor].wrapping_add(1),
+            b'-' => cells[cursor] = cells[cursor].wrapping_sub(1),
+            b'>' => {
+                cursor += 1;
+                if cursor == cells.len() {
+                    cells.push(0);
+                }
+            }
+            b'<' => cursor = cursor.saturating_sub(1),
+            b'[' if cells[cursor] == 0 => {
+                let mut depth = 0;
+                loop {
+                    match code[ip] {
+                        b'[' => depth += 1,
+                        b']' => depth -= 1,
+                        _ => (),
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    ip += 1;
+                }
+            }
+            b']' if cells[cursor] != 0 => {
+                let mut depth = 0;
+                loop {
+                    match code[ip] {
+                        b']' => depth += 1,
+                        b'[' => depth -= 1,
+                        _ => (),
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    ip -= 1;
+                }
+            }
+            b'.' => output.push(cells[cursor]),
+            b',' => cells[cursor] = input.pop_front().unwrap_or(AUTO_INPUT),
+            _ => (),
+        }
+        ip += 1;
+        steps += 1;
+    }
+
+    Snapshot {
+        output,
+        cells,
+        cursor,
+        finished: ip >= code.len(),
+    }
+}
+
+/// Generates programs with balanced brackets, sprinkled with comment bytes
+/// that must be ignored.
+fn balanced_program() -> impl Strategy<Value = Vec<u8>> {
+    let leaf = prop_oneof![
+        8 => prop::sample::select(b"+-<>.,".to_vec()).prop_map(|b| vec![b]),
+        1 => prop::sample::select(b" \n#abc!".to_vec()).prop_map(|b| vec![b]),
+    ];
+    leaf.prop_recursive(4, 128, 12, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..12)
+                .prop_map(|v| v.concat()),
+            prop::collection::vec(inner, 0..12).prop_map(|v| {
+                let mut body = vec![b'['];
+                body.extend(v.concat());
+                body.push(b']');
+                body
+            }),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn engines_agree_on_balanced_programs(
+        code in balanced_program(),
+        input in prop::collection::vec(any::<u8>(), 0..16),
+    ) {
+        let expected = snapshot_reference(&code, &input);
+        for (name, engine) in ENGINES {
+            let actual = engine(&code, &input);
+            prop_assert_eq!(&actual, &expected, "engine: {}", name);
+        }
+    }
+
+    #[test]
+    fn sanitize_keeps_only_instructions(code in any::<Vec<u8>>()) {
+        let instructions =
+            Interpreter::sanitize(code.iter().copied(), Extension::None);
+        prop_assert!(instructions.iter().all(|c| b"+-<>[].,".contains(c)));
+        prop_assert_eq!(
+            instructions.len(),
+            code.iter().filter(|c| b"+-<>[].,".contains(c)).count()
+        );
+    }
+
+    #[test]
+    fn build_bracemap_pairs_are_symmetric(code in any::<Vec<u8>>()) {
+        let instructions =
+            Interpreter::sanitize(code.into_iter(), Extension::None);
+        let bracemap = Interpreter::build_bracemap(&instructions);
+        for (&from, &to) in &bracemap {
+            prop_assert_eq!(bracemap.get(&to), Some(&from));
+            let (open, close) = (from.min(to), from.max(to));
+            prop_assert_eq!(instructions[open], b'[');
+            prop_assert_eq!(instructions[close], b']');
+        }
+    }
+
+    #[test]
+    fn unmatched_bracket_is_a_mismatched_brackets_error(
+        code in balanced_program(),
+    ) {
+        // Only programs that finish within the limit reach the extra bracket
+        prop_assume!(snapshot_reference(&code, &[]).finished);
+        let code = code.into_iter().chain(*b"]");
+        let mut int =
+            Interpreter::new(code, VecDeque::new(), Some(AUTO_INPUT));
+        let err = int.by_ref().take(MAX_STEPS + 1).find_map(Result::err);
+        prop_assert_eq!(
+            err.as_ref().and_then(|err| err.downcast_ref::<ErrorKind>()),
+            Some(&ErrorKind::MismatchedBrackets)
+        );
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_panic(
+        code in any::<Vec<u8>>(),
+        input in any::<Vec<u8>>(),
+    ) {
+        let mut int = Interpreter::new(
+            code.into_iter(),
+            input.into(),
+            Some(AUTO_INPUT),
+        );
+        for frame in int.by_ref().take(MAX_STEPS) {
+            if frame.is_err() {
+                break;
+            }
+        }
+    }
+}
@@ -4,18 +4,54 @@ use std::{
     io::{self, Read},
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 
-use super::tape::Tape;
+use super::{
+    brackets::Brackets,
+    error::ErrorKind,
+    extension::{Extension, Schedule},
+    options::Options,
+    tape::Tape,
+};
+
+/// The deepest that pbrain procedure calls may nest.
+const MAX_CALL_DEPTH: usize = 1 << 16;
+
+/// A Brainfork thread that is waiting for its turn.
+#[derive(Clone, Copy, Debug)]
+struct Thread {
+    ip: usize,
+    cursor: usize,
+}
 
 #[derive(Debug)]
 pub struct Interpreter {
     instructions: Vec<u8>,
     bracemap: HashMap<usize, usize>,
+    options: Options,
+    /// The matching parenthesis of each pbrain procedure definition.
+    procmap: HashMap<usize, usize>,
+    /// The start of the body of each defined pbrain procedure.
+    procedures: HashMap<u8, usize>,
+    /// Where to return to from each pbrain procedure being executed.
+    call_stack: Vec<usize>,
+    /// The Brainfork threads other than the current one, in the order they
+    /// will run.
+    threads: VecDeque<Thread>,
+    schedule: Schedule,
+    /// How many more instructions the current thread executes in its turn.
+    slice: usize,
     ip: usize,
     pub tape: Tape,
     pub input: VecDeque<u8>,
     auto_input: Option<u8>,
+    bytes_read: usize,
+    /// The Boolfuck input byte being read, and how many of its bits are
+    /// left.
+    input_bits: (u8, u32),
+    /// The Boolfuck output byte being written, and how many of its bits are
+    /// written.
+    output_bits: (u8, u32),
     pub output: Vec<u8>,
 }
 
@@ -25,48 +61,212 @@ impl Interpreter {
         input: VecDeque<u8>,
         auto_input: Option<u8>,
     ) -> Self {
-        let instructions = Self::sanitize(code);
+        Self::with_options(code, input, auto_input, Options::default())
+    }
+
+    /// Create an interpreter that also executes the instructions of an
+    /// extension to Brainfuck.
+    pub fn with_extension(
+        code: impl Iterator<Item = u8>,
+        input: VecDeque<u8>,
+        auto_input: Option<u8>,
+        extension: Extension,
+    ) -> Self {
+        let options = Options {
+            extension,
+            ..Options::default()
+        };
+        Self::with_options(code, input, auto_input, options)
+    }
+
+    /// Create an interpreter that reads the script as described by
+    /// `options`, e.g. [`Options::compat`].
+    pub fn with_options(
+        code: impl Iterator<Item = u8>,
+        mut input: VecDeque<u8>,
+        auto_input: Option<u8>,
+        options: Options,
+    ) -> Self {
+        let extension = options.extension;
+        let mut code = code.collect::<Vec<_>>();
+        if options.embedded_input {
+            if let Some(i) = code.iter().position(|&b| b == b'!') {
+                for &b in code[i + 1..].iter().rev() {
+                    input.push_front(b);
+                }
+                code.truncate(i);
+            }
+        }
+        let instructions = Self::sanitize(code.into_iter(), extension);
         let bracemap = Self::build_bracemap(&instructions);
+        let procmap = match extension {
+            Extension::Pbrain => Self::build_pairs(&instructions, b'(', b')'),
+            _ => HashMap::new(),
+        };
+        let tape = match extension {
+            Extension::Boolfuck => Tape::bits(),
+            _ => Tape::default(),
+        };
         Self {
             instructions,
             bracemap,
+            options,
+            procmap,
+            procedures: HashMap::new(),
+            call_stack: Vec::new(),
+            threads: VecDeque::new(),
+            schedule: Schedule::default(),
+            slice: 1,
             ip: 0,
-            tape: Tape::default(),
+            tape,
             input,
             auto_input,
+            bytes_read: 0,
+            input_bits: (0, 0),
+            output_bits: (0, 0),
             output: Vec::new(),
         }
     }
 
-    fn sanitize(code: impl Iterator<Item = u8>) -> Vec<u8> {
-        code.filter(|c| {
-            matches!(*c as char, '+' | '-' | '>' | '<' | '[' | ']' | '.' | ',')
-        })
-        .collect()
+    pub const fn is_instruction(byte: u8) -> bool {
+        matches!(byte, b'+' | b'-' | b'>' | b'<' | b'[' | b']' | b'.' | b',')
     }
 
-    fn build_bracemap(instructions: &[u8]) -> HashMap<usize, usize> {
-        let mut open_brackets = Vec::new();
-        let mut bracemap = HashMap::new();
+    pub const fn extension(&self) -> Extension {
+        self.options.extension
+    }
 
-        for (i, v) in instructions.iter().map(|i| *i as char).enumerate() {
-            if v == '[' {
-                open_brackets.push(i);
-            } else if v == ']' {
-                if let Some(open_i) = open_brackets.pop() {
-                    bracemap.insert(open_i, i);
-                    bracemap.insert(i, open_i);
-                }
-            }
-        }
+    pub const fn options(&self) -> Options {
+        self.options
+    }
+
+    /// Choose how Brainfork threads take turns. The default is
+    /// [`Schedule::RoundRobin`].
+    pub const fn set_schedule(&mut self, schedule: Schedule) {
+        (self.slice, self.schedule) = schedule.next_slice();
+    }
+
+    /// The cursor of each Brainfork thread, starting with the thread that
+    /// is running. Without Brainfork this is only the tape cursor.
+    pub fn cursors(&self) -> Vec<usize> {
+        let waiting = self.threads.iter().map(|thread| thread.cursor);
+        std::iter::once(self.tape.cursor()).chain(waiting).collect()
+    }
 
-        bracemap
+    pub(super) fn sanitize(
+        code: impl Iterator<Item = u8>,
+        extension: Extension,
+    ) -> Vec<u8> {
+        code.filter(|c| extension.is_instruction(*c)).collect()
+    }
+
+    pub(super) fn build_bracemap(
+        instructions: &[u8],
+    ) -> HashMap<usize, usize> {
+        Self::build_pairs(instructions, b'[', b']')
+    }
+
+    /// Map each `open` to its matching `close` and back.
+    fn build_pairs(
+        instructions: &[u8],
+        open: u8,
+        close: u8,
+    ) -> HashMap<usize, usize> {
+        let code = instructions.iter().copied().enumerate();
+        Brackets::resolve(code, open, close).pairs
     }
 
     fn jump_bracket(&self) -> Result<usize> {
         match self.bracemap.get(&self.ip) {
             Some(next) => Ok(next + 1),
-            None => Err(anyhow!("mismatched brackets")),
+            None => Err(ErrorKind::MismatchedBrackets.into()),
+        }
+    }
+
+    /// Define the procedure numbered by the current cell, and skip over its
+    /// body.
+    fn define_procedure(&mut self) -> Result<usize> {
+        let Some(&close) = self.procmap.get(&self.ip) else {
+            return Err(ErrorKind::MismatchedBrackets.into());
+        };
+        let number = self.tape.current().value();
+        self.procedures.insert(number, self.ip + 1);
+        Ok(close + 1)
+    }
+
+    fn call_procedure(&mut self) -> Result<usize> {
+        let number = self.tape.current().value();
+        let Some(&start) = self.procedures.get(&number) else {
+            return Err(ErrorKind::UndefinedProcedure(number).into());
+        };
+        if self.call_stack.len() == MAX_CALL_DEPTH {
+            return Err(ErrorKind::CallStackOverflow.into());
+        }
+        self.call_stack.push(self.ip + 1);
+        Ok(start)
+    }
+
+    fn return_procedure(&mut self) -> Result<usize> {
+        if !self.procmap.contains_key(&self.ip) {
+            return Err(ErrorKind::MismatchedBrackets.into());
+        }
+        // The body of a procedure is only executed when it is called
+        Ok(self.call_stack.pop().unwrap_or(self.ip + 1))
+    }
+
+    /// Start a child thread on the next cell, which is set to 1, and set
+    /// the current cell to 0.
+    fn fork(&mut self) {
+        let cursor = self.tape.cursor();
+        self.tape.current().set(0);
+        self.tape.seek(cursor + 1);
+        self.tape.current().set(1);
+        self.tape.seek(cursor);
+        self.threads.push_back(Thread {
+            ip: self.ip + 1,
+            cursor: cursor + 1,
+        });
+    }
+
+    /// Give the next thread its turn, putting the current thread at the
+    /// back of the queue unless it has finished.
+    fn switch_thread(&mut self, finished: bool) -> bool {
+        let Some(next) = self.threads.pop_front() else {
+            return false;
+        };
+        if !finished {
+            self.threads.push_back(Thread {
+                ip: self.ip,
+                cursor: self.tape.cursor(),
+            });
+        }
+        self.ip = next.ip;
+        self.tape.seek(next.cursor);
+        (self.slice, self.schedule) = self.schedule.next_slice();
+        true
+    }
+
+    /// Read the next bit of the input, reading a new byte when every bit of
+    /// the last one has been read.
+    fn read_bit(&mut self) -> Result<u8> {
+        let (mut byte, mut left) = self.input_bits;
+        if left == 0 {
+            byte = self.read_char()?;
+            left = 8;
+            self.bytes_read += 1;
+        }
+        self.input_bits = (byte >> 1, left - 1);
+        Ok(byte & 1)
+    }
+
+    fn write_bit(&mut self, bit: u8) {
+        let (byte, written) = self.output_bits;
+        let byte = byte | bit << written;
+        if written == 7 {
+            self.output.push(byte);
+            self.output_bits = (0, 0);
+        } else {
+            self.output_bits = (byte, written + 1);
         }
     }
 
@@ -78,14 +278,37 @@ impl Interpreter {
                 let mut buf = [0u8; 1];
                 match io::stdin().read_exact(&mut buf) {
                     Ok(_) => Ok(buf[0]),
-                    Err(e) => {
-                        Err(e).context("failed to read character from stdin")
-                    }
+                    Err(e) => Err(e).context(ErrorKind::ReadInput),
                 }
             }
         }
     }
 
+    /// Mark the end of the input, so that every later read (,) gives 0
+    /// instead of reading stdin once the queued input runs out.
+    pub const fn end_input(&mut self) {
+        self.auto_input = Some(0);
+    }
+
+    pub const fn ip(&self) -> usize {
+        self.ip
+    }
+
+    /// The number of bytes consumed by the read (,) instruction so far.
+    pub const fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+
+    /// Whether the next instruction reads input that has not been given,
+    /// so that it would block on stdin. In Boolfuck, a read (,) takes the
+    /// remaining bits of the last byte before it needs a new one.
+    pub fn needs_input(&self) -> bool {
+        self.peek() == Some(',')
+            && self.input.is_empty()
+            && self.input_bits.1 == 0
+            && self.auto_input.is_none()
+    }
+
     pub fn peek(&self) -> Option<char> {
         if self.instructions.is_empty()
             || self.ip > self.instructions.len() - 1
@@ -96,7 +319,7 @@ impl Interpreter {
         }
     }
 
-    pub fn output(&self) -> Cow<str> {
+    pub fn output(&self) -> Cow<'_, str> {
         String::from_utf8_lossy(&self.output)
     }
 
@@ -108,11 +331,17 @@ impl Interpreter {
 impl Iterator for Interpreter {
     type Item = Result<char>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.instructions.is_empty()
-            || self.ip > self.instructions.len() - 1
-        {
-            // No instructions or end of program
-            return None;
+        while self.ip >= self.instructions.len() {
+            // No instructions or end of program, unless another thread is
+            // still running
+            if !self.switch_thread(true) {
+                // A partly written Boolfuck byte is padded with zeros
+                if self.output_bits.1 > 0 {
+                    self.output.push(self.output_bits.0);
+                    self.output_bits = (0, 0);
+                }
+                return None;
+            }
         }
 
         let ins = self.instructions[self.ip] as char;
@@ -142,15 +371,42 @@ impl Iterator for Interpreter {
                 }
             }
             '.' => self.output.push(self.tape.current().value()),
+            ',' if self.options.extension == Extension::Boolfuck => {
+                match self.read_bit() {
+                    Ok(bit) => self.tape.current().set(bit),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            ';' => {
+                let bit = self.tape.current().value();
+                self.write_bit(bit);
+            }
             ',' => match self.read_char() {
-                Ok(c) => self.tape.current().set(c),
+                Ok(c) => {
+                    self.tape.current().set(c);
+                    self.bytes_read += 1;
+                }
                 Err(e) => return Some(Err(e)),
             },
+            '(' => next_ip = self.define_procedure(),
+            ')' => next_ip = self.return_procedure(),
+            ':' => next_ip = self.call_procedure(),
+            'Y' => self.fork(),
             _ => return None,
         }
 
         if let Ok(ip) = next_ip {
             self.ip = ip;
+            self.slice = self.slice.saturating_sub(1);
+            let running = self.ip < self.instructions.len();
+            if running && self.slice == 0 && !self.switch_thread(false) {
+                self.slice = 1;
+            }
+            // Hand finished threads over right away, so that `ip` always
+            // points at the next instruction to execute
+            while self.ip >= self.instructions.len()
+                && self.switch_thread(true)
+            {}
         }
         Some(next_ip.map(|_| ins))
     }
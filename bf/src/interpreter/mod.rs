@@ -1,8 +1,23 @@
+mod brackets;
+pub use brackets::Brackets;
+
 mod cell;
 
+mod error;
+pub use error::ErrorKind;
+
+mod extension;
+pub use extension::{Extension, Schedule, MAX_SLICE};
+
+#[cfg(test)]
+mod fuzz;
+
 #[allow(clippy::module_inception)]
 mod interpreter;
 pub use interpreter::Interpreter;
 
+mod options;
+pub use options::Options;
+
 mod tape;
-pub use tape::Tape;
+pub use tape::{CurrentCell, Tape};
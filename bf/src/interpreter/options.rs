@@ -0,0 +1,54 @@
+use super::extension::Extension;
+
+/// How an [`Interpreter`](super::Interpreter) reads a script.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    pub extension: Extension,
+    /// Split the code at the first `!` and read everything after it as
+    /// input, before any other input.
+    pub embedded_input: bool,
+}
+
+impl Options {
+    /// The conventions followed by many published Brainfuck programs, such
+    /// as input embedded after a `!`.
+    pub const fn compat() -> Self {
+        Self {
+            extension: Extension::None,
+            embedded_input: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Options;
+    use crate::interpreter::Interpreter;
+
+    fn run(code: &str, input: &str, options: Options) -> Vec<u8> {
+        let mut int = Interpreter::with_options(
+            code.bytes(),
+            input.bytes().collect(),
+            Some(0),
+            options,
+        );
+        for frame in int.by_ref() {
+            frame.unwrap();
+        }
+        int.output
+    }
+
+    #[test]
+    fn embedded_input() {
+        let compat = Options::compat();
+        assert_eq!(run(",[.,]!hi", "", compat), b"hi");
+        // Only the first `!` separates the input, which comes first
+        assert_eq!(run(",[.,]!a!b", "c", compat), b"a!bc");
+        assert_eq!(run(",[.,]!.", "", compat), b".");
+    }
+
+    #[test]
+    fn embedded_input_is_opt_in() {
+        assert_eq!(run(",[.,]!hi", "x", Options::default()), b"x");
+    }
+}
@@ -2,51 +2,159 @@ use super::cell::Cell;
 
 #[derive(Clone, Debug)]
 pub struct Tape {
-    cells: Vec<Cell>,
+    cells: Cells,
     cursor: usize,
 }
 
+/// How the cells of a tape are stored.
+#[derive(Clone, Debug)]
+enum Cells {
+    Bytes(Vec<Cell>),
+    /// Single-bit cells, packed 64 to a word, for Boolfuck.
+    Bits {
+        words: Vec<u64>,
+        len: usize,
+    },
+}
+
+impl Cells {
+    fn value(&self, index: usize) -> u8 {
+        match self {
+            Self::Bytes(cells) => cells[index].value(),
+            Self::Bits { words, .. } => {
+                ((words[index / 64] >> (index % 64)) & 1) as u8
+            }
+        }
+    }
+}
+
+/// The cell under the cursor, see [`Tape::current`].
+pub struct CurrentCell<'tape> {
+    cells: &'tape mut Cells,
+    index: usize,
+}
+
 impl Default for Tape {
     fn default() -> Self {
         Self {
-            cells: vec![Cell::new(); 1],
+            cells: Cells::Bytes(vec![Cell::new(); 1]),
             cursor: 0,
         }
     }
 }
 
 impl Tape {
-    pub fn cells(&self) -> &[Cell] {
-        &self.cells
+    /// Create a tape of single-bit cells. Incrementing or decrementing a
+    /// bit flips it.
+    pub fn bits() -> Self {
+        Self {
+            cells: Cells::Bits {
+                words: vec![0],
+                len: 1,
+            },
+            cursor: 0,
+        }
+    }
+
+    pub const fn is_bits(&self) -> bool {
+        matches!(self.cells, Cells::Bits { .. })
+    }
+
+    /// The value of the cell at `index`, which must be within the tape.
+    pub fn value(&self, index: usize) -> u8 {
+        assert!(index < self.len(), "cell {index} is past the tape");
+        self.cells.value(index)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len()).map(|i| self.value(i))
     }
 
     #[allow(clippy::len_without_is_empty)]
-    pub fn len(&self) -> usize {
-        self.cells.len()
+    pub const fn len(&self) -> usize {
+        match &self.cells {
+            Cells::Bytes(cells) => cells.len(),
+            Cells::Bits { len, .. } => *len,
+        }
     }
 
-    fn get(&mut self, index: usize) -> &mut Cell {
-        while index > self.cells.len() - 1 {
-            self.cells.push(Cell::new());
+    /// Extend the tape so that it includes `index`.
+    fn extend_to(&mut self, index: usize) {
+        match &mut self.cells {
+            Cells::Bytes(cells) => {
+                while index > cells.len() - 1 {
+                    cells.push(Cell::new());
+                }
+            }
+            Cells::Bits { words, len } => {
+                *len = (*len).max(index + 1);
+                words.resize(len.div_ceil(64), 0);
+            }
         }
-        &mut self.cells[index]
     }
 
-    pub fn current(&mut self) -> &mut Cell {
-        self.get(self.cursor)
+    pub fn current(&mut self) -> CurrentCell<'_> {
+        self.extend_to(self.cursor);
+        CurrentCell {
+            cells: &mut self.cells,
+            index: self.cursor,
+        }
     }
 
     pub const fn cursor(&self) -> usize {
         self.cursor
     }
 
-    pub fn left(&mut self) {
+    pub const fn left(&mut self) {
         self.cursor = self.cursor.saturating_sub(1);
     }
 
+    /// Move the cursor to `index`, extending the tape if needed.
+    pub fn seek(&mut self, index: usize) {
+        self.cursor = index;
+        self.extend_to(index);
+    }
+
     pub fn right(&mut self) {
         self.cursor += 1;
-        // Force tape to be extended
-        self.current();
+        self.extend_to(self.cursor);
+    }
+}
+
+impl CurrentCell<'_> {
+    pub fn inc(&mut self) {
+        match self.cells {
+            Cells::Bytes(cells) => cells[self.index].inc(),
+            Cells::Bits { words, .. } => {
+                words[self.index / 64] ^= 1 << (self.index % 64);
+            }
+        }
+    }
+
+    pub fn dec(&mut self) {
+        match self.cells {
+            Cells::Bytes(cells) => cells[self.index].dec(),
+            Cells::Bits { .. } => self.inc(),
+        }
+    }
+
+    pub fn value(&self) -> u8 {
+        self.cells.value(self.index)
+    }
+
+    /// Set the cell to `value`, or to its lowest bit on a tape of bits.
+    pub fn set(&mut self, value: u8) {
+        match self.cells {
+            Cells::Bytes(cells) => cells[self.index].set(value),
+            Cells::Bits { words, .. } => {
+                let mask = 1 << (self.index % 64);
+                let word = &mut words[self.index / 64];
+                if value & 1 == 1 {
+                    *word |= mask;
+                } else {
+                    *word &= !mask;
+                }
+            }
+        }
     }
 }
@@ -2,4 +2,8 @@
 #![warn(clippy::nursery)]
 #![allow(clippy::multiple_crate_versions, clippy::option_if_let_else)]
 
+pub mod asm;
+
+pub mod dialect;
+
 pub mod interpreter;
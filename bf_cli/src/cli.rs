@@ -24,13 +24,13 @@ pub fn parse_infile(value: &str) -> Result<PathBuf, ClapError> {
     }
 }
 
-pub fn parse_width(value: &str) -> Result<u64, ClapError> {
+pub fn parse_width(value: &str) -> Result<usize, ClapError> {
     match value.parse::<i64>() {
         Ok(n) => {
             if n < 5 {
-                Err("value must be an integer > 5".into())
+                Err("value must be an integer >= 5".into())
             } else {
-                Ok(n as u64)
+                Ok(n as usize)
             }
         }
         Err(err) => Err(err.into()),
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::cli::{parse_infile, SubCmd};
+
+use super::subcmd_asm;
+
+const ABOUT: &str = "Compile a program in the bf assembly language to \
+                     Brainfuck";
+const LONG_ABOUT: &str = "Compile a program in the bf assembly language to \
+                          Brainfuck.
+
+Each line holds one statement, and ; or # starts a comment:
+
+  cell a, b         name cells, in order from cell 0
+  set a 'A'         set a cell to a number or a character
+  add a 2           add to (or sub from) a cell, wrapping at 256
+  clear a           set a cell to 0
+  move a b          set b to a and a to 0
+  copy a b          set b to a
+  in a              read a byte into a cell
+  out a             print a cell as a byte
+  print \"hi\\n\"      print a string
+  printnum a        print a cell as a decimal number
+  if a ... end      run the body once if a is not 0
+  while a ... end   run the body as long as a is not 0
+  macro m x ... end define a macro, called as `m a`
+
+Temporary cells are allocated automatically after the named cells.";
+const OUTFILE_HELP: &str = "The file to which the Brainfuck script is \
+                            written. If none is given print to stdout.";
+const MAP_HELP: &str = "Write a JSON source map to this file: the names of \
+                        the named cells by their index, and the line of the \
+                        assembly that produced each Brainfuck instruction.";
+const INFILE_HELP: &str = "The path to the assembly source. Read from stdin \
+                           if infile is a dash (-) or is omitted.";
+
+#[derive(Debug, Parser)]
+#[command(about=ABOUT, long_about=LONG_ABOUT)]
+pub struct AsmCli {
+    #[arg(short, long, help=OUTFILE_HELP)]
+    pub outfile: Option<PathBuf>,
+
+    #[arg(short, long, help=MAP_HELP)]
+    pub map: Option<PathBuf>,
+
+    #[arg(value_parser=parse_infile, help=INFILE_HELP)]
+    pub infile: Option<PathBuf>,
+}
+
+impl SubCmd for AsmCli {
+    fn run(self) -> Result<()> {
+        subcmd_asm(self)
+    }
+}
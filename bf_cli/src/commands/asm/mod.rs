@@ -0,0 +1,52 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use bf::asm::assemble;
+use serde::Serialize;
+
+use crate::{err_file_open, err_file_write, utils::read::read_script};
+
+pub use self::cli::AsmCli;
+
+mod cli;
+
+/// The source map written by `--map`.
+#[derive(Serialize)]
+struct SourceMap<'a> {
+    /// The name of each named cell, by its index on the tape.
+    cells: &'a [String],
+    /// The 1-based line of the assembly for each instruction of the script.
+    lines: &'a [usize],
+}
+
+pub fn subcmd_asm(args: AsmCli) -> Result<()> {
+    let source = read_script(args.infile.as_ref())?;
+    let source = String::from_utf8(source)
+        .context("the assembly source is not valid UTF-8")?;
+
+    let asm = assemble(&source)?;
+
+    match &args.outfile {
+        Some(path) => fs::write(path, &asm.code)
+            .with_context(|| err_file_write!(path))?,
+        None => io::stdout()
+            .write_all(asm.code.as_bytes())
+            .with_context(|| err_file_write!(PathBuf::from("STDOUT")))?,
+    }
+
+    if let Some(path) = &args.map {
+        let map = SourceMap {
+            cells: &asm.cells,
+            lines: &asm.lines,
+        };
+        let file = File::create(path).with_context(|| err_file_open!(path))?;
+        serde_json::to_writer(file, &map)
+            .with_context(|| err_file_write!(path))?;
+    }
+
+    Ok(())
+}
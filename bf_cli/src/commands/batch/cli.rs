@@ -0,0 +1,91 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::cli::{ClapError, SubCmd};
+
+use super::subcmd_batch;
+
+const ABOUT: &str = "Run many Brainfuck scripts in parallel and summarize \
+                     the results";
+const JOBS_HELP: &str = "The number of scripts to run at once. Defaults to \
+                         the number of CPUs.";
+const MAX_STEPS_HELP: &str = "The maximum number of instructions each script \
+                              may execute before it is stopped.";
+const TIMEOUT_HELP: &str = "The maximum number of seconds each script may \
+                            run before it is stopped.";
+const EOF_HELP: &str = "The value read by the read (,) instruction once the \
+                        input is exhausted.";
+const INPUT_EXT_HELP: &str = "The extension of each script's input file. The \
+                              input for foo.bf is read from foo.in, if it \
+                              exists.";
+const INPUT_DIR_HELP: &str = "The directory in which to look for input \
+                              files, laid out like the scripts below the \
+                              directory that contains all of them. Defaults \
+                              to the directory of each script.";
+const OUTPUT_DIR_HELP: &str = "The directory to which each script's output \
+                               is written, as <name>.out, laid out like the \
+                               scripts below the directory that contains \
+                               all of them. Defaults to the directory of \
+                               each script.";
+const SCRIPTS_HELP: &str = "The scripts to run. Glob patterns, such as \
+                            'submissions/*.bf', are expanded.";
+
+fn parse_jobs(value: &str) -> Result<usize, ClapError> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("value must be an integer > 0".into()),
+        Ok(n) => Ok(n),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn parse_timeout(value: &str) -> Result<Duration, ClapError> {
+    match value.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => {
+            Ok(Duration::from_secs_f64(secs))
+        }
+        Ok(_) => Err("value must be a number > 0".into()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(about=ABOUT)]
+pub struct BatchCli {
+    #[arg(short, long, value_parser=parse_jobs, help=JOBS_HELP)]
+    pub jobs: Option<usize>,
+
+    #[arg(short, long, default_value="10000000", help=MAX_STEPS_HELP)]
+    pub max_steps: u64,
+
+    #[arg(
+        short,
+        long,
+        default_value="10",
+        value_parser=parse_timeout,
+        help=TIMEOUT_HELP
+    )]
+    pub timeout: Duration,
+
+    #[arg(short, long, default_value="0", help=EOF_HELP)]
+    pub eof_value: u8,
+
+    #[arg(long, default_value="in", help=INPUT_EXT_HELP)]
+    pub input_ext: String,
+
+    #[arg(short, long, help=INPUT_DIR_HELP)]
+    pub input_dir: Option<PathBuf>,
+
+    #[arg(short, long, help=OUTPUT_DIR_HELP)]
+    pub output_dir: Option<PathBuf>,
+
+    #[arg(required=true, help=SCRIPTS_HELP)]
+    pub scripts: Vec<String>,
+}
+
+impl SubCmd for BatchCli {
+    fn run(self) -> Result<()> {
+        subcmd_batch(self)
+    }
+}
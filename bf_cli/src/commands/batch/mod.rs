@@ -0,0 +1,364 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    io::{self, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use bf::interpreter::Interpreter;
+
+use crate::{
+    err_file_read, err_file_write, err_print, utils::read::read_script_file,
+};
+
+pub use self::cli::BatchCli;
+
+mod cli;
+
+/// How often, in instructions, a running script checks the time limit.
+const TIMEOUT_CHECK_INTERVAL: u64 = 4096;
+
+enum Status {
+    Done,
+    Error(String),
+    StepLimit,
+    Timeout,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Done => f.write_str("done"),
+            Self::Error(err) => write!(f, "error: {err}"),
+            Self::StepLimit => f.write_str("step limit"),
+            Self::Timeout => f.write_str("timeout"),
+        }
+    }
+}
+
+struct ScriptResult {
+    status: Status,
+    steps: u64,
+    time: Duration,
+}
+
+/// Expand the glob patterns, keeping paths that aren't patterns as is.
+fn expand_scripts(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut scripts = Vec::new();
+    for pattern in patterns {
+        if Path::new(pattern).exists() {
+            scripts.push(PathBuf::from(pattern));
+            continue;
+        }
+        let matches = glob::glob(pattern)
+            .with_context(|| format!("invalid glob pattern: {pattern}"))?
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("failed to expand: {pattern}"))?;
+        if matches.is_empty() {
+            bail!("no such file or matching files: {pattern}");
+        }
+        scripts.extend(matches.into_iter().filter(|path| path.is_file()));
+    }
+    Ok(scripts)
+}
+
+/// The path of each script relative to the deepest directory that contains
+/// all of them, so that scripts with the same name in different directories,
+/// such as `alice/main.bf` and `bob/main.bf`, are kept apart in the input
+/// and output directories.
+fn relative_paths(scripts: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let paths = scripts
+        .iter()
+        .map(|path| {
+            fs::canonicalize(path).with_context(|| err_file_read!(path))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut root = paths
+        .first()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    for path in &paths {
+        while !path.starts_with(&root) && root.pop() {}
+    }
+
+    Ok(paths
+        .iter()
+        .map(|path| path.strip_prefix(&root).unwrap_or(path).to_path_buf())
+        .collect())
+}
+
+/// The path next to the script, or at its relative path in `dir` if given,
+/// with the extension `ext`.
+fn sibling_path(
+    script: &Path,
+    relative: &Path,
+    dir: Option<&PathBuf>,
+    ext: &str,
+) -> PathBuf {
+    match dir {
+        Some(dir) => dir.join(relative).with_extension(ext),
+        None => script.with_extension(ext),
+    }
+}
+
+fn run_script(
+    args: &BatchCli,
+    path: &Path,
+    relative: &Path,
+) -> Result<ScriptResult> {
+    let code = read_script_file(path)?;
+
+    let input_path =
+        sibling_path(path, relative, args.input_dir.as_ref(), &args.input_ext);
+    let input = if input_path.is_file() {
+        fs::read(&input_path).with_context(|| err_file_read!(input_path))?
+    } else {
+        Vec::new()
+    };
+
+    let mut interpreter =
+        Interpreter::new(code.into_iter(), input.into(), Some(args.eof_value));
+
+    let start = Instant::now();
+    let mut steps = 0;
+    let status = loop {
+        if steps == args.max_steps {
+            break Status::StepLimit;
+        }
+        if steps % TIMEOUT_CHECK_INTERVAL == 0
+            && start.elapsed() > args.timeout
+        {
+            break Status::Timeout;
+        }
+        match interpreter.next() {
+            Some(Ok(_)) => steps += 1,
+            Some(Err(err)) => break Status::Error(err.to_string()),
+            None => break Status::Done,
+        }
+    };
+    let time = start.elapsed();
+
+    let output_path =
+        sibling_path(path, relative, args.output_dir.as_ref(), "out");
+    if let Some(dir) = output_path.parent() {
+        fs::create_dir_all(dir).with_context(|| err_file_write!(dir))?;
+    }
+    fs::write(&output_path, interpreter.output_bytes())
+        .with_context(|| err_file_write!(output_path))?;
+
+    Ok(ScriptResult {
+        status,
+        steps,
+        time,
+    })
+}
+
+/// Run every script on `jobs` worker threads, returning the results in the
+/// same order as the scripts.
+fn run_all(
+    args: &BatchCli,
+    scripts: &[PathBuf],
+    relative: &[PathBuf],
+    jobs: usize,
+) -> Vec<ScriptResult> {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = scripts.get(i) else {
+                    break;
+                };
+                let result = run_script(args, path, &relative[i])
+                    .unwrap_or_else(|err| ScriptResult {
+                        status: Status::Error(format!("{err:#}")),
+                        steps: 0,
+                        time: Duration::ZERO,
+                    });
+                // The receiver outlives the workers
+                sender.send((i, result)).ok();
+            });
+        }
+    });
+    drop(sender);
+
+    let mut results = receiver.into_iter().collect::<Vec<_>>();
+    results.sort_unstable_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn write_summary(
+    out: &mut impl Write,
+    scripts: &[PathBuf],
+    results: &[ScriptResult],
+) -> io::Result<()> {
+    let names = scripts
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>();
+    let width = names.iter().map(String::len).max().unwrap_or(0).max(6);
+
+    writeln!(
+        out,
+        "{:width$}  {:>12}  {:>10}  Status",
+        "Script", "Steps", "Time"
+    )?;
+    for (name, result) in names.iter().zip(results) {
+        writeln!(
+            out,
+            "{name:width$}  {:>12}  {:>9.3}s  {}",
+            result.steps,
+            result.time.as_secs_f64(),
+            result.status,
+        )?;
+    }
+    Ok(())
+}
+
+pub fn subcmd_batch(args: BatchCli) -> Result<()> {
+    let scripts = expand_scripts(&args.scripts)?;
+    if scripts.is_empty() {
+        bail!("no scripts to run");
+    }
+
+    for dir in [&args.input_dir, &args.output_dir].into_iter().flatten() {
+        if !dir.is_dir() {
+            bail!("no such directory exists: {}", dir.display());
+        }
+    }
+
+    let jobs = args
+        .jobs
+        .unwrap_or_else(|| {
+            thread::available_parallelism().map_or(1, NonZeroUsize::get)
+        })
+        .min(scripts.len());
+
+    let relative = relative_paths(&scripts)?;
+    let results = run_all(&args, &scripts, &relative, jobs);
+
+    write_summary(&mut io::stdout().lock(), &scripts, &results)
+        .with_context(|| err_print!())?;
+
+    let failed = results
+        .iter()
+        .filter(|result| !matches!(result.status, Status::Done))
+        .count();
+    if failed > 0 {
+        bail!("{failed} of {} scripts did not finish", scripts.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process,
+    };
+
+    use clap::Parser;
+
+    use super::{relative_paths, run_all, BatchCli, Status};
+
+    /// An empty directory for the files of a test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            env::temp_dir().join(format!("bf-batch-{}-{name}", process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn args(extra: &[&Path]) -> BatchCli {
+        let args = ["batch", "-m", "100"].map(Path::new);
+        BatchCli::parse_from(args.iter().chain(extra).chain([&Path::new("-")]))
+    }
+
+    #[test]
+    fn paths_are_relative_to_the_common_directory() {
+        let dir = temp_dir("relative");
+        let scripts = ["a/x.bf", "a/b/y.bf"].map(|path| dir.join(path));
+        scripts.iter().for_each(|path| write(path, ""));
+
+        let relative = relative_paths(&scripts).unwrap();
+        assert_eq!(relative, ["x.bf", "b/y.bf"].map(PathBuf::from));
+        // A lone script keeps only its name
+        let relative = relative_paths(&scripts[1..]).unwrap();
+        assert_eq!(relative, [PathBuf::from("y.bf")]);
+    }
+
+    #[test]
+    fn scripts_with_the_same_name_are_kept_apart() {
+        let dir = temp_dir("same-name");
+        let scripts = ["alice/main.bf", "bob/main.bf"]
+            .map(|path| dir.join("scripts").join(path));
+        write(&scripts[0], ",.");
+        write(&scripts[1], ",+.");
+        write(&dir.join("inputs/alice/main.in"), "a");
+        write(&dir.join("inputs/bob/main.in"), "b");
+        let (inputs, outputs) = (dir.join("inputs"), dir.join("outputs"));
+        fs::create_dir(&outputs).unwrap();
+
+        let args =
+            args(&[Path::new("-i"), &inputs, Path::new("-o"), &outputs]);
+        let relative = relative_paths(&scripts).unwrap();
+        let results = run_all(&args, &scripts, &relative, 2);
+
+        assert!(results.iter().all(|r| matches!(r.status, Status::Done)));
+        assert_eq!(fs::read(outputs.join("alice/main.out")).unwrap(), b"a");
+        assert_eq!(fs::read(outputs.join("bob/main.out")).unwrap(), b"c");
+    }
+
+    #[test]
+    fn results_are_in_the_order_of_the_scripts() {
+        let dir = temp_dir("order");
+        let scripts = ["+.", "+]", "+[]", "++"]
+            .iter()
+            .enumerate()
+            .map(|(i, code)| {
+                let path = dir.join(format!("{i}.bf"));
+                write(&path, code);
+                path
+            })
+            .collect::<Vec<_>>();
+
+        let relative = relative_paths(&scripts).unwrap();
+        let results = run_all(&args(&[]), &scripts, &relative, 3);
+
+        let statuses = results.iter().map(|r| &r.status).collect::<Vec<_>>();
+        assert!(matches!(
+            statuses[..],
+            [
+                Status::Done,
+                Status::Error(_),
+                Status::StepLimit,
+                Status::Done
+            ]
+        ));
+        let steps = results.iter().map(|r| r.steps).collect::<Vec<_>>();
+        assert_eq!(steps, [2, 1, 100, 2]);
+        assert_eq!(fs::read(dir.join("0.out")).unwrap(), b"\x01");
+    }
+}
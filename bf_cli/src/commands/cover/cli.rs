@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::cli::{parse_infile, SubCmd};
+
+use super::subcmd_cover;
+
+const ABOUT: &str = "Report which instructions and loops of a Brainfuck \
+                     script are executed by a set of inputs";
+const INPUT_HELP: &str = "A file whose contents are given to the script as \
+                          input. The script is run once per input file. If \
+                          none are given the script is run once with no \
+                          input.";
+const EOF_HELP: &str = "The value read by the read (,) instruction once the \
+                        input is exhausted.";
+const MAX_STEPS_HELP: &str = "The maximum number of instructions to execute \
+                              per input before the run is stopped.";
+const LCOV_HELP: &str = "The file to which an lcov tracefile of the \
+                         coverage is written.";
+const INFILE_HELP: &str = "The path to the Brainfuck script to cover.";
+
+#[derive(Debug, Parser)]
+#[command(about=ABOUT)]
+pub struct CoverCli {
+    #[arg(short, long="input", value_parser=parse_infile, help=INPUT_HELP)]
+    pub inputs: Vec<PathBuf>,
+
+    #[arg(short, long, default_value="0", help=EOF_HELP)]
+    pub eof_value: u8,
+
+    #[arg(short, long, default_value="1000000", help=MAX_STEPS_HELP)]
+    pub max_steps: u64,
+
+    #[arg(short, long, help=LCOV_HELP)]
+    pub lcov: Option<PathBuf>,
+
+    #[arg(value_parser=parse_infile, help=INFILE_HELP)]
+    pub infile: PathBuf,
+}
+
+impl SubCmd for CoverCli {
+    fn run(self) -> Result<()> {
+        subcmd_cover(self)
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use anyhow::Error;
+use bf::interpreter::Interpreter;
+
+/// How many times a loop was entered and how many times it was skipped when
+/// its opening bracket was reached.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoopHits {
+    pub entered: u64,
+    pub skipped: u64,
+}
+
+pub enum RunOutcome {
+    Done,
+    Error(Error),
+    StepLimit,
+}
+
+pub struct Coverage {
+    /// Execution count of each instruction, indexed by instruction pointer.
+    pub hits: Vec<u64>,
+    /// Loop counts, keyed by the instruction pointer of the opening bracket.
+    pub loops: BTreeMap<usize, LoopHits>,
+}
+
+impl Coverage {
+    pub fn new(instructions: impl Iterator<Item = u8>) -> Self {
+        let mut hits = Vec::new();
+        let mut loops = BTreeMap::new();
+        for (ip, ins) in instructions.enumerate() {
+            hits.push(0);
+            if ins == b'[' {
+                loops.insert(ip, LoopHits::default());
+            }
+        }
+        Self { hits, loops }
+    }
+
+    /// Run the interpreter to completion, or until `max_steps` instructions
+    /// have executed, counting every instruction along the way.
+    pub fn record(
+        &mut self,
+        int: &mut Interpreter,
+        max_steps: u64,
+    ) -> (RunOutcome, u64) {
+        let mut steps = 0;
+        loop {
+            if steps == max_steps {
+                break (RunOutcome::StepLimit, steps);
+            }
+
+            let ip = int.ip();
+            let is_loop_start = int.peek() == Some('[');
+
+            match int.next() {
+                None => break (RunOutcome::Done, steps),
+                Some(Err(err)) => break (RunOutcome::Error(err), steps),
+                Some(Ok(_)) => {}
+            }
+
+            steps += 1;
+            self.hits[ip] += 1;
+            if is_loop_start {
+                let loop_hits = self.loops.entry(ip).or_default();
+                if int.ip() == ip + 1 {
+                    loop_hits.entered += 1;
+                } else {
+                    loop_hits.skipped += 1;
+                }
+            }
+        }
+    }
+
+    pub fn covered(&self) -> usize {
+        self.hits.iter().filter(|&&h| h > 0).count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bf::interpreter::{Extension, Interpreter};
+
+    use crate::utils::{read::script_lines, source_map::SourceMap};
+
+    use super::{Coverage, RunOutcome};
+
+    const SCRIPT: &[u8] = b"++ two\n[>+<-]>[-]\n<[+] never";
+
+    fn record(max_steps: u64) -> (Coverage, RunOutcome, u64) {
+        let instructions = SCRIPT.iter().copied();
+        let mut coverage = Coverage::new(
+            instructions
+                .clone()
+                .filter(|b| Interpreter::is_instruction(*b)),
+        );
+        let mut int = Interpreter::new(instructions, [].into(), Some(0));
+        let (outcome, steps) = coverage.record(&mut int, max_steps);
+        (coverage, outcome, steps)
+    }
+
+    #[test]
+    fn counts_instructions_and_loops() {
+        let (coverage, outcome, steps) = record(100);
+        assert!(matches!(outcome, RunOutcome::Done));
+        assert_eq!(steps, 21);
+        assert_eq!(
+            coverage.hits,
+            [1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 2, 2, 1, 1, 0, 0]
+        );
+        assert_eq!(coverage.covered(), 14);
+
+        let loops = coverage
+            .loops
+            .iter()
+            .map(|(ip, hits)| (*ip, hits.entered, hits.skipped))
+            .collect::<Vec<_>>();
+        assert_eq!(loops, [(2, 1, 0), (9, 1, 0), (13, 0, 1)]);
+    }
+
+    #[test]
+    fn stops_at_the_step_limit() {
+        let (coverage, outcome, steps) = record(5);
+        assert!(matches!(outcome, RunOutcome::StepLimit));
+        assert_eq!(steps, 5);
+        assert_eq!(coverage.hits.iter().sum::<u64>(), 5);
+    }
+
+    #[test]
+    fn instruction_pointers_map_to_the_source() {
+        let map = SourceMap::new(&script_lines(SCRIPT), Extension::None);
+        let position = |ip| map.get(ip).map(|pos| (pos.line, pos.column));
+        assert_eq!(position(1), Some((0, 1)));
+        assert_eq!(position(2), Some((1, 0)));
+        assert_eq!(position(13), Some((2, 1)));
+        assert_eq!(position(16), None);
+    }
+}
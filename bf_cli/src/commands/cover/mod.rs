@@ -0,0 +1,93 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use bf::interpreter::{Extension, Interpreter};
+
+use crate::{
+    err_file_open, err_file_read, err_file_write,
+    utils::{
+        read::{read_script_file, script_lines},
+        source_map::SourceMap,
+    },
+};
+
+pub use self::cli::CoverCli;
+use self::coverage::{Coverage, RunOutcome};
+
+mod cli;
+
+mod coverage;
+
+mod report;
+
+pub fn subcmd_cover(args: CoverCli) -> Result<()> {
+    let code = read_script_file(&args.infile)?;
+    let script = script_lines(&code);
+    let source_map = SourceMap::new(&script, Extension::None);
+
+    let inputs = if args.inputs.is_empty() {
+        vec![(PathBuf::from("(no input)"), Vec::new())]
+    } else {
+        args.inputs
+            .into_iter()
+            .map(|path| {
+                let data =
+                    fs::read(&path).with_context(|| err_file_read!(path))?;
+                Ok((path, data))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut coverage = Coverage::new(
+        code.iter()
+            .copied()
+            .filter(|b| Interpreter::is_instruction(*b)),
+    );
+
+    for (path, input) in inputs {
+        let mut int = Interpreter::new(
+            code.iter().copied(),
+            input.into(),
+            Some(args.eof_value),
+        );
+        let (outcome, steps) = coverage.record(&mut int, args.max_steps);
+        let name = path.display();
+        match outcome {
+            RunOutcome::Done => {
+                eprintln!("{name}: finished in {steps} steps");
+            }
+            RunOutcome::StepLimit => {
+                eprintln!("{name}: stopped at the step limit ({steps} steps)");
+            }
+            RunOutcome::Error(err) => {
+                eprintln!("{name}: error after {steps} steps: {err}");
+            }
+        }
+    }
+    eprintln!();
+
+    let stdout = io::stdout();
+    let color = stdout.is_terminal();
+    report::write_text(
+        &mut stdout.lock(),
+        &script,
+        &source_map,
+        &coverage,
+        color,
+    )
+    .with_context(|| err_file_write!(PathBuf::from("STDOUT")))?;
+
+    if let Some(path) = &args.lcov {
+        let file = File::create(path).with_context(|| err_file_open!(path))?;
+        let mut writer = BufWriter::new(file);
+        report::write_lcov(&mut writer, &args.infile, &source_map, &coverage)
+            .and_then(|()| writer.flush())
+            .with_context(|| err_file_write!(path))?;
+    }
+
+    Ok(())
+}
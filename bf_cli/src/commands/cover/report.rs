@@ -0,0 +1,229 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::Path,
+};
+
+use crossterm::style::Stylize;
+
+use crate::utils::source_map::SourceMap;
+
+use super::coverage::Coverage;
+
+const UNCOVERED_MARKER: &str = "#####";
+
+/// Per-line execution counts: the highest count of any instruction on the
+/// line, along with the columns of the instructions that never ran.
+struct LineCoverage {
+    hits: u64,
+    uncovered: Vec<usize>,
+}
+
+fn line_coverage(
+    map: &SourceMap,
+    coverage: &Coverage,
+) -> BTreeMap<usize, LineCoverage> {
+    let mut lines = BTreeMap::<usize, LineCoverage>::new();
+    for (pos, &hits) in map.iter().zip(&coverage.hits) {
+        let line = lines.entry(pos.line).or_insert(LineCoverage {
+            hits: 0,
+            uncovered: Vec::new(),
+        });
+        line.hits = line.hits.max(hits);
+        if hits == 0 {
+            line.uncovered.push(pos.column);
+        }
+    }
+    lines
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        100.0 * part as f64 / total as f64
+    }
+}
+
+pub fn write_text(
+    out: &mut impl Write,
+    script: &[String],
+    map: &SourceMap,
+    coverage: &Coverage,
+    color: bool,
+) -> io::Result<()> {
+    let lines = line_coverage(map, coverage);
+
+    for (i, text) in script.iter().enumerate() {
+        let Some(line) = lines.get(&i) else {
+            writeln!(out, "{:>7} | {text}", "-")?;
+            continue;
+        };
+
+        if line.hits == 0 {
+            write!(out, "{UNCOVERED_MARKER:>7} | ")?;
+        } else {
+            write!(out, "{:>7} | ", line.hits)?;
+        }
+
+        if color {
+            for (column, c) in text.char_indices() {
+                if line.uncovered.contains(&column) {
+                    write!(out, "{}", c.red().bold())?;
+                } else {
+                    write!(out, "{c}")?;
+                }
+            }
+            writeln!(out)?;
+        } else {
+            writeln!(out, "{text}")?;
+            if !line.uncovered.is_empty() && line.hits > 0 {
+                // Point at the instructions that never ran
+                let mut markers = String::new();
+                for (column, c) in text.char_indices() {
+                    if line.uncovered.contains(&column) {
+                        markers.push('^');
+                    } else if c == '\t' {
+                        markers.push('\t');
+                    } else {
+                        markers.push(' ');
+                    }
+                }
+                writeln!(out, "{:>7} | {}", "", markers.trim_end())?;
+            }
+        }
+    }
+
+    let total = coverage.hits.len();
+    let covered = coverage.covered();
+    let loops = coverage.loops.len();
+    let entered = coverage.loops.values().filter(|l| l.entered > 0).count();
+    let skipped = coverage.loops.values().filter(|l| l.skipped > 0).count();
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "Instructions: {covered}/{total} ({:.2}%)",
+        percent(covered, total)
+    )?;
+    writeln!(
+        out,
+        "Loops:        {entered}/{loops} entered, {skipped}/{loops} skipped",
+    )?;
+
+    Ok(())
+}
+
+/// Write the coverage in the lcov tracefile format. Each loop is reported as
+/// a branch with two arms: entered (0) and skipped (1).
+pub fn write_lcov(
+    out: &mut impl Write,
+    script_path: &Path,
+    map: &SourceMap,
+    coverage: &Coverage,
+) -> io::Result<()> {
+    let lines = line_coverage(map, coverage);
+
+    writeln!(out, "TN:")?;
+    writeln!(out, "SF:{}", script_path.display())?;
+
+    let mut branches_hit = 0;
+    for (block, (&ip, hits)) in coverage.loops.iter().enumerate() {
+        let Some(pos) = map.get(ip) else { continue };
+        let line = pos.line + 1;
+        // A branch that was never reached is reported as `-`
+        let reached = hits.entered + hits.skipped > 0;
+        for (branch, taken) in [hits.entered, hits.skipped].iter().enumerate()
+        {
+            if reached {
+                writeln!(out, "BRDA:{line},{block},{branch},{taken}")?;
+            } else {
+                writeln!(out, "BRDA:{line},{block},{branch},-")?;
+            }
+            if *taken > 0 {
+                branches_hit += 1;
+            }
+        }
+    }
+    writeln!(out, "BRF:{}", 2 * coverage.loops.len())?;
+    writeln!(out, "BRH:{branches_hit}")?;
+
+    for (line, line_cov) in &lines {
+        writeln!(out, "DA:{},{}", line + 1, line_cov.hits)?;
+    }
+    writeln!(out, "LF:{}", lines.len())?;
+    writeln!(out, "LH:{}", lines.values().filter(|l| l.hits > 0).count())?;
+
+    writeln!(out, "end_of_record")
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use bf::interpreter::{Extension, Interpreter};
+
+    use crate::utils::{read::script_lines, source_map::SourceMap};
+
+    use super::{write_lcov, write_text, Coverage};
+
+    // The last loop is skipped, and the last line has no instructions
+    const SCRIPT: &[u8] = b"++ two\n[>+<-]>[-]\n<[+] never\nthe end";
+
+    fn record() -> (Vec<String>, SourceMap, Coverage) {
+        let lines = script_lines(SCRIPT);
+        let map = SourceMap::new(&lines, Extension::None);
+        let instructions = SCRIPT.iter().copied();
+        let mut coverage = Coverage::new(
+            instructions
+                .clone()
+                .filter(|b| Interpreter::is_instruction(*b)),
+        );
+        let mut int = Interpreter::new(instructions, [].into(), Some(0));
+        coverage.record(&mut int, 100);
+        (lines, map, coverage)
+    }
+
+    #[test]
+    fn text_report() {
+        let (lines, map, coverage) = record();
+        let mut out = Vec::new();
+        write_text(&mut out, &lines, &map, &coverage, false).unwrap();
+        let expected = "      1 | ++ two
+      2 | [>+<-]>[-]
+      1 | <[+] never
+        |   ^^
+      - | the end
+
+Instructions: 14/16 (87.50%)
+Loops:        2/3 entered, 1/3 skipped
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn lcov_report() {
+        let (_, map, coverage) = record();
+        let mut out = Vec::new();
+        let path = Path::new("script.b");
+        write_lcov(&mut out, path, &map, &coverage).unwrap();
+        let expected = "TN:
+SF:script.b
+BRDA:2,0,0,1
+BRDA:2,0,1,0
+BRDA:2,1,0,1
+BRDA:2,1,1,0
+BRDA:3,2,0,0
+BRDA:3,2,1,1
+BRF:6
+BRH:3
+DA:1,1
+DA:2,2
+DA:3,1
+LF:3
+LH:3
+end_of_record
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::cli::{parse_infile, SubCmd};
+
+use super::subcmd_equiv;
+
+const ABOUT: &str = "Check whether two Brainfuck scripts behave the same \
+                     on a set of inputs";
+const INPUT_HELP: &str = "A file whose contents are given to both scripts \
+                          as input. May be given more than once.";
+const RANDOM_HELP: &str = "The number of randomly generated inputs to try \
+                           in addition to the given input files.";
+const MAX_LEN_HELP: &str = "The maximum length, in bytes, of each randomly \
+                            generated input.";
+const SEED_HELP: &str = "The seed for generating random inputs. A random \
+                         seed is used if none is given.";
+const EOF_HELP: &str = "The value read by the read (,) instruction once the \
+                        input is exhausted.";
+const MAX_STEPS_HELP: &str = "The maximum number of instructions each script \
+                              may execute per input before it is stopped.";
+const LEFT_HELP: &str = "The path to the first Brainfuck script.";
+const RIGHT_HELP: &str = "The path to the second Brainfuck script.";
+
+#[derive(Debug, Parser)]
+#[command(about=ABOUT)]
+pub struct EquivCli {
+    #[arg(short, long="input", value_parser=parse_infile, help=INPUT_HELP)]
+    pub inputs: Vec<PathBuf>,
+
+    #[arg(short, long, default_value="100", help=RANDOM_HELP)]
+    pub random: usize,
+
+    #[arg(short='l', long, default_value="16", help=MAX_LEN_HELP)]
+    pub max_len: usize,
+
+    #[arg(short, long, help=SEED_HELP)]
+    pub seed: Option<u64>,
+
+    #[arg(short, long, default_value="0", help=EOF_HELP)]
+    pub eof_value: u8,
+
+    #[arg(short, long, default_value="1000000", help=MAX_STEPS_HELP)]
+    pub max_steps: u64,
+
+    #[arg(value_parser=parse_infile, help=LEFT_HELP)]
+    pub left: PathBuf,
+
+    #[arg(value_parser=parse_infile, help=RIGHT_HELP)]
+    pub right: PathBuf,
+}
+
+impl SubCmd for EquivCli {
+    fn run(self) -> Result<()> {
+        subcmd_equiv(self)
+    }
+}
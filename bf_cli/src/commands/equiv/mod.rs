@@ -0,0 +1,271 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+};
+
+use anyhow::{bail, Context, Result};
+use bf::interpreter::Interpreter;
+
+use crate::{err_file_read, utils::read::read_script_file};
+
+pub use self::cli::EquivCli;
+
+mod cli;
+
+#[derive(PartialEq, Eq)]
+enum Termination {
+    Done,
+    Error(String),
+    StepLimit,
+}
+
+impl Display for Termination {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Done => write!(f, "finished"),
+            Self::Error(err) => write!(f, "error: {err}"),
+            Self::StepLimit => write!(f, "stopped at the step limit"),
+        }
+    }
+}
+
+struct Run {
+    termination: Termination,
+    output: Vec<u8>,
+    steps: u64,
+}
+
+impl Run {
+    fn new(code: &[u8], input: &[u8], eof_value: u8, max_steps: u64) -> Self {
+        let mut int = Interpreter::new(
+            code.iter().copied(),
+            input.iter().copied().collect(),
+            Some(eof_value),
+        );
+
+        let mut steps = 0;
+        let termination = loop {
+            if steps == max_steps {
+                break Termination::StepLimit;
+            }
+            match int.next() {
+                None => break Termination::Done,
+                Some(Err(err)) => break Termination::Error(err.to_string()),
+                Some(Ok(_)) => steps += 1,
+            }
+        };
+
+        Self {
+            termination,
+            output: int.output,
+            steps,
+        }
+    }
+}
+
+/// Where two runs of the same input first diverge.
+#[derive(Debug, PartialEq, Eq)]
+struct Divergence {
+    offset: Option<usize>,
+    termination: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Comparison {
+    Agree,
+    /// A run stopped at the step limit before it could diverge.
+    Inconclusive,
+    Diverge(Divergence),
+}
+
+fn compare(left: &Run, right: &Run) -> Comparison {
+    let mismatch = left
+        .output
+        .iter()
+        .zip(&right.output)
+        .position(|(l, r)| l != r);
+
+    // A run that hit the step limit only gives a prefix of its output, so
+    // the runs diverge only if it disagrees with the other output or is
+    // already longer than the final output of the other run
+    let limited = |run: &Run| run.termination == Termination::StepLimit;
+    if limited(left) || limited(right) {
+        let stopped = match (limited(left), limited(right)) {
+            (false, true) => Some((left, right)),
+            (true, false) => Some((right, left)),
+            _ => None,
+        };
+        let offset = mismatch.or_else(|| {
+            stopped
+                .filter(|(done, cut)| cut.output.len() > done.output.len())
+                .map(|(done, _)| done.output.len())
+        });
+        return offset.map_or(Comparison::Inconclusive, |offset| {
+            Comparison::Diverge(Divergence {
+                offset: Some(offset),
+                termination: false,
+            })
+        });
+    }
+
+    let offset = mismatch.or_else(|| {
+        (left.output.len() != right.output.len())
+            .then(|| left.output.len().min(right.output.len()))
+    });
+    let termination = left.termination != right.termination;
+
+    if offset.is_some() || termination {
+        Comparison::Diverge(Divergence {
+            offset,
+            termination,
+        })
+    } else {
+        Comparison::Agree
+    }
+}
+
+fn escape(bytes: &[u8]) -> String {
+    bytes.escape_ascii().to_string()
+}
+
+fn describe_byte(output: &[u8], offset: usize) -> String {
+    match output.get(offset) {
+        Some(b) => format!("{b:#04x} ({})", escape(&[*b])),
+        None => String::from("end of output"),
+    }
+}
+
+pub fn subcmd_equiv(args: EquivCli) -> Result<()> {
+    let left = read_script_file(&args.left)?;
+    let right = read_script_file(&args.right)?;
+
+    let mut inputs = args
+        .inputs
+        .iter()
+        .map(|path| {
+            let data = fs::read(path).with_context(|| err_file_read!(path))?;
+            Ok((path.display().to_string(), data))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let seed = args.seed.unwrap_or_else(|| fastrand::u64(..));
+    let mut rng = fastrand::Rng::with_seed(seed);
+    for i in 0..args.random {
+        let len = rng.usize(..=args.max_len);
+        let data = (0..len).map(|_| rng.u8(..)).collect();
+        inputs.push((format!("random input #{i} (seed {seed})"), data));
+    }
+
+    let mut inconclusive = 0;
+    for (name, input) in &inputs {
+        let l = Run::new(&left, input, args.eof_value, args.max_steps);
+        let r = Run::new(&right, input, args.eof_value, args.max_steps);
+
+        let divergence = match compare(&l, &r) {
+            Comparison::Agree => continue,
+            Comparison::Inconclusive => {
+                inconclusive += 1;
+                continue;
+            }
+            Comparison::Diverge(divergence) => divergence,
+        };
+
+        println!("The scripts differ on {name}: \"{}\"", escape(input));
+        for (path, run) in [(&args.left, &l), (&args.right, &r)] {
+            println!(
+                "  {}: {} after {} steps, {} bytes of output",
+                path.display(),
+                run.termination,
+                run.steps,
+                run.output.len(),
+            );
+        }
+        if let Some(offset) = divergence.offset {
+            println!(
+                "  outputs differ at byte {offset}: {} vs {}",
+                describe_byte(&l.output, offset),
+                describe_byte(&r.output, offset),
+            );
+        }
+        if divergence.termination {
+            println!("  termination differs");
+        }
+        bail!("the scripts are not equivalent");
+    }
+
+    let agreed = inputs.len() - inconclusive;
+    println!("The scripts agree on {agreed} of {} inputs", inputs.len());
+    if inconclusive > 0 {
+        println!(
+            "  the other {inconclusive} were inconclusive because a script \
+             hit the step limit before they diverged"
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compare, Comparison, Divergence, Run, Termination};
+
+    fn run(termination: Termination, output: &[u8]) -> Run {
+        Run {
+            termination,
+            output: output.to_vec(),
+            steps: 0,
+        }
+    }
+
+    const fn diverge_at(offset: usize) -> Comparison {
+        Comparison::Diverge(Divergence {
+            offset: Some(offset),
+            termination: false,
+        })
+    }
+
+    #[test]
+    fn finished_runs() {
+        let done = |output| run(Termination::Done, output);
+        assert_eq!(compare(&done(b"ab"), &done(b"ab")), Comparison::Agree);
+        assert_eq!(compare(&done(b"ab"), &done(b"ax")), diverge_at(1));
+        assert_eq!(compare(&done(b"ab"), &done(b"a")), diverge_at(1));
+        let error = run(Termination::Error(String::new()), b"ab");
+        assert_eq!(
+            compare(&done(b"ab"), &error),
+            Comparison::Diverge(Divergence {
+                offset: None,
+                termination: true,
+            })
+        );
+    }
+
+    #[test]
+    fn finished_and_limited_runs() {
+        let done = run(Termination::Done, b"abc");
+        let limited = |output| run(Termination::StepLimit, output);
+        // The limited run may still print the rest
+        assert_eq!(compare(&done, &limited(b"ab")), Comparison::Inconclusive);
+        assert_eq!(compare(&limited(b""), &done), Comparison::Inconclusive);
+        assert_eq!(compare(&done, &limited(b"abc")), Comparison::Inconclusive);
+        // But it has already printed more than the finished run
+        assert_eq!(compare(&limited(b"abcd"), &done), diverge_at(3));
+    }
+
+    #[test]
+    fn limited_runs() {
+        let limited = |output| run(Termination::StepLimit, output);
+        assert_eq!(
+            compare(&limited(b"a"), &limited(b"abc")),
+            Comparison::Inconclusive
+        );
+        assert_eq!(compare(&limited(b"axc"), &limited(b"ab")), diverge_at(1));
+    }
+
+    #[test]
+    fn prefix_mismatch_with_a_limited_run() {
+        let done = run(Termination::Done, b"abc");
+        let limited = run(Termination::StepLimit, b"ax");
+        assert_eq!(compare(&done, &limited), diverge_at(1));
+    }
+}
@@ -1,17 +1,37 @@
 use std::path::PathBuf;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{builder::PossibleValuesParser, Parser};
 
-use crate::cli::SubCmd;
+use crate::cli::{parse_width, SubCmd};
 
-use super::subcmd_generate;
+use super::{
+    script::Format,
+    subcmd_generate,
+    transform::{EOF_CONVENTIONS, TRANSFORM_MODES},
+};
 
 const ABOUT: &str =
-    "Generate a Brainfuck script that prints the given text file \
-    (aliases: gen, g)";
+    "Generate a Brainfuck script that prints the given text file, or that \
+    transforms its input (aliases: gen, g)";
 const NEWLINE_HELP: &str =
     "Append a final newline to the data if it is missing.";
+const OPTIMIZE_HELP: &str =
+    "Search for the loop counters that give the shortest script, and report \
+    the size saved compared to the default output.";
+const VERIFY_HELP: &str =
+    "Execute the generated script and check that it prints the data exactly.";
+const STATS_HELP: &str =
+    "Print the script length, instruction count, steps executed and tape \
+    cells used by the generated script to stderr.";
+const WRAP_HELP: &str = "Wrap the lines of the script at this many columns.";
+const CELL_LINES_HELP: &str =
+    "Put the setup of each cell and each block of prints on its own line.";
+const INDENT_HELP: &str =
+    "Put the body of each loop on its own lines, indented by the loop depth.";
+const ANNOTATE_HELP: &str =
+    "Add comments describing the byte each cell holds and the text each \
+    block of prints outputs.";
 const OUTFILE_HELP: &str =
     "The file to which the generated script is written. If none is given \
 print to stdout.";
@@ -24,12 +44,36 @@ const MODE_HELP_LONG: &str =
     memory; each cell is printed in order. linewise (one loop per line): \
     similar to charwise except lines are created in memory then printed one \
     at a time. unique-chars (one loop): each unique byte in the data gets a \
-    cell in memory.";
-const INFILE_HELP: &str =
+    cell in memory. delta (one loop): a few working cells are changed from \
+    one byte to the next, using whichever cell is cheapest to reach and \
+    adjust. best: the shortest script of all the other modes. all: compare \
+    the scripts of every mode side by side instead of writing one. \
+    The transform modes generate a script that reads its input until EOF \
+    instead: cat prints the input as is, rev prints it reversed, upper, lower \
+    and rot13 change the case of or rotate ASCII letters, count-bytes prints \
+    the number of bytes read in decimal, and tr FROM TO replaces each byte \
+    in the FROM set with the byte at the same position in the TO set, as the \
+    tr command does. Sets may contain ranges such as a-z and the escape \
+    sequences \\n, \\t, \\xHH etc.";
+const ARGS_HELP: &str =
     "The file that will be printed by the generated script. If none is given \
-    read from stdin.";
+    read from stdin. The tr mode takes its FROM and TO sets instead, and the \
+    other transform modes take nothing.";
+const EOF_HELP: &str =
+    "What the interpreter that will run a transform script does on `,` at \
+    the end of input: set the cell to 0, set it to 255 (minus-one), or leave \
+    it unchanged. With zero and unchanged the script stops at a null byte, \
+    with minus-one at a 255 byte.";
 
-const GEN_MODES: [&str; 3] = ["charwise", "linewise", "unique-chars"];
+pub const GEN_MODES: [&str; 5] =
+    ["charwise", "linewise", "unique-chars", "delta", "best"];
+
+/// Every generation mode, `all` to compare them, and the transform modes.
+fn mode_parser() -> PossibleValuesParser {
+    PossibleValuesParser::new(
+        GEN_MODES.into_iter().chain(["all"]).chain(TRANSFORM_MODES),
+    )
+}
 
 #[derive(Debug, Parser)]
 #[structopt(about=ABOUT)]
@@ -37,14 +81,59 @@ pub struct GenerateCli {
     #[arg(short, long, help=NEWLINE_HELP)]
     pub newline: bool,
 
+    #[arg(short='O', long, help=OPTIMIZE_HELP)]
+    pub optimize: bool,
+
+    #[arg(long, help=VERIFY_HELP)]
+    pub verify: bool,
+
+    #[arg(short, long, help=STATS_HELP)]
+    pub stats: bool,
+
+    #[arg(short, long, value_parser=parse_width, help=WRAP_HELP)]
+    pub wrap: Option<usize>,
+
+    #[arg(short, long, help=CELL_LINES_HELP)]
+    pub cell_lines: bool,
+
+    #[arg(short, long, help=INDENT_HELP)]
+    pub indent: bool,
+
+    #[arg(short, long, help=ANNOTATE_HELP)]
+    pub annotate: bool,
+
     #[arg(short, long, help=OUTFILE_HELP)]
     pub outfile: Option<PathBuf>,
 
-    #[arg(value_parser=GEN_MODES, help=MODE_HELP, long_help=MODE_HELP_LONG)]
+    #[arg(
+        short,
+        long,
+        default_value="zero",
+        value_parser=EOF_CONVENTIONS,
+        help=EOF_HELP
+    )]
+    pub eof: String,
+
+    #[arg(
+        value_parser=mode_parser(),
+        help=MODE_HELP,
+        long_help=MODE_HELP_LONG
+    )]
     pub mode: String,
 
-    #[arg(help=INFILE_HELP)]
-    pub infile: Option<PathBuf>,
+    #[arg(value_name="INFILE | FROM TO", help=ARGS_HELP)]
+    pub args: Vec<String>,
+}
+
+impl GenerateCli {
+    pub const fn format(&self) -> Format {
+        Format {
+            wrap: self.wrap,
+            cell_lines: self.cell_lines,
+            indent: self.indent,
+            annotate: self.annotate,
+        }
+    }
 }
 
 impl SubCmd for GenerateCli {
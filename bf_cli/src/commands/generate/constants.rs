@@ -0,0 +1,174 @@
+//! Shortest-constant search for setting up the data cells.
+//!
+//! Each cell is set with a multiplication loop followed by a small
+//! adjustment, `value = factor * multiplier + adjustment`, where every term
+//! may wrap around. The multiplier is either a single loop counter or the
+//! product of two nested counters, and the construction that gives the
+//! shortest code for the whole group of cells is chosen.
+
+use std::{collections::HashMap, iter};
+
+use super::script::{describe_byte, Script};
+
+/// The largest loop counter tried for a single loop.
+const MAX_COUNTER: usize = 64;
+
+/// The largest counter tried for each of the two nested loops.
+const MAX_NESTED_COUNTER: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Construction {
+    /// No loop, each cell is set with its adjustment alone.
+    Flat,
+    /// `+`*k `[` factors `-]`
+    Single(usize),
+    /// `+`*a `[>` `+`*b `[` factors `-]<-]>`, which leaves the values one
+    /// cell further to the right.
+    Nested(usize, usize),
+}
+
+impl Construction {
+    const fn multiplier(self) -> usize {
+        match self {
+            Self::Flat => 0,
+            Self::Single(k) => k,
+            Self::Nested(a, b) => a * b,
+        }
+    }
+
+    /// The length of the loop code, excluding the factors of each cell.
+    const fn overhead(self, cells: usize) -> usize {
+        match self {
+            Self::Flat => 0,
+            Self::Single(k) => k + 2 * cells + 3,
+            Self::Nested(a, b) => a + b + 2 * cells + 8,
+        }
+    }
+
+    fn candidates() -> impl Iterator<Item = Self> {
+        let single = (2..=MAX_COUNTER).map(Self::Single);
+        let nested = (2..=MAX_NESTED_COUNTER).flat_map(|a| {
+            (2..=MAX_NESTED_COUNTER).map(move |b| Self::Nested(a, b))
+        });
+        iter::once(Self::Flat).chain(single).chain(nested)
+    }
+}
+
+/// The shortest signed difference that takes a cell from `from` to `to`,
+/// wrapping around if that is shorter.
+pub const fn wrapping_diff(from: u8, to: u8) -> isize {
+    let diff = (to as isize - from as isize).rem_euclid(256);
+    if diff <= 128 {
+        diff
+    } else {
+        diff - 256
+    }
+}
+
+/// The loop factor and the adjustment that set a cell to `value` in the
+/// fewest instructions for the given multiplier.
+fn best_factor(value: u8, multiplier: usize) -> (isize, isize) {
+    if multiplier == 0 {
+        return (0, wrapping_diff(0, value));
+    }
+    let max = (256 / multiplier + 1) as isize;
+    (-max..=max)
+        .map(|factor| {
+            let approx = (factor * multiplier as isize).rem_euclid(256);
+            (factor, wrapping_diff(approx as u8, value))
+        })
+        .min_by_key(|&(factor, adj)| (factor.abs() + adj.abs(), factor.abs()))
+        .unwrap_or_else(|| (0, wrapping_diff(0, value)))
+}
+
+pub fn push_diff(script: &mut Script, diff: isize) {
+    let c = if diff < 0 { '-' } else { '+' };
+    script.push_n(c, diff.unsigned_abs());
+}
+
+fn choose_construction(values: &[u8]) -> Construction {
+    let mut counts = [0_usize; 256];
+    for &value in values {
+        counts[value as usize] += 1;
+    }
+
+    let mut cell_costs = HashMap::<usize, usize>::new();
+    let mut cost = |construction: Construction| {
+        let multiplier = construction.multiplier();
+        let cells = *cell_costs.entry(multiplier).or_insert_with(|| {
+            (0..=u8::MAX)
+                .filter(|&value| counts[value as usize] > 0)
+                .map(|value| {
+                    let (factor, adj) = best_factor(value, multiplier);
+                    let cost = factor.unsigned_abs() + adj.unsigned_abs();
+                    cost * counts[value as usize]
+                })
+                .sum()
+        });
+        construction.overhead(values.len()) + cells
+    };
+
+    // `min_by_key` keeps the first of equal candidates, the simplest one
+    Construction::candidates()
+        .min_by_key(|&construction| cost(construction))
+        .unwrap_or(Construction::Flat)
+}
+
+/// Generate the shortest code found that sets the cells to the right of the
+/// current cell to `values`. The current cell, and the cell to its right in
+/// case nested loops are used, must be 0. The cursor ends on the 0 cell
+/// immediately to the left of the values.
+pub fn gen_cells(script: &mut Script, values: &[u8]) {
+    let construction = choose_construction(values);
+    let factors = values
+        .iter()
+        .map(|&value| best_factor(value, construction.multiplier()))
+        .collect::<Vec<_>>();
+    let len = values.len();
+
+    let push_factors = |script: &mut Script| {
+        script.line();
+        for &(factor, _) in &factors {
+            script.push('>');
+            push_diff(script, factor);
+            script.line();
+        }
+        script.push_n('<', len);
+    };
+
+    match construction {
+        Construction::Flat => {}
+        Construction::Single(k) => {
+            script.push_n('+', k);
+            script.push('[');
+            push_factors(script);
+            script.push_str("-]");
+            script.line();
+        }
+        Construction::Nested(a, b) => {
+            script.push_n('+', a);
+            script.push_str("[>");
+            script.push_n('+', b);
+            script.push('[');
+            push_factors(script);
+            script.push_str("-]<-]>");
+            script.line();
+        }
+    }
+
+    // Adjust cells to real value
+    for (i, (&(_, adj), &value)) in factors.iter().zip(values).enumerate() {
+        script.comment(format!(
+            "cell {} holds {}",
+            i + 1,
+            describe_byte(value)
+        ));
+        script.push('>');
+        push_diff(script, adj);
+        script.line();
+    }
+
+    // Go back to beginning
+    script.push_n('<', len);
+    script.line();
+}
@@ -2,33 +2,115 @@ use std::{
     collections::{HashMap, HashSet},
     fs::File,
     io::{self, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{bail, Context, Result};
 
-use crate::{err_file_open, err_file_write};
+use crate::{err_file_open, err_file_write, err_print};
 
 pub use self::cli::GenerateCli;
-use self::read::read_data;
+use self::{
+    cli::GEN_MODES,
+    constants::{gen_cells, push_diff, wrapping_diff},
+    read::read_data,
+    script::{describe_byte, describe_text, Script},
+    stats::{write_table, Stats},
+    transform::{gen_transform, Eof, TRANSFORM_MODES},
+};
 
 mod cli;
 
+mod constants;
+
 mod read;
 
+mod script;
+
+mod stats;
+
+mod transform;
+
 #[cfg(windows)]
 pub const EOL: &str = "\r\n";
 #[cfg(not(windows))]
 pub const EOL: &str = "\n";
 
 pub fn subcmd_generate(args: GenerateCli) -> Result<()> {
-    let mut data = read_data(args.infile)?;
+    if TRANSFORM_MODES.contains(&args.mode.as_str()) {
+        return transform(args);
+    }
+
+    let infile = match args.args.as_slice() {
+        [] => None,
+        [path] => Some(Path::new(path)),
+        _ => bail!("the {} mode takes at most one file", args.mode),
+    };
+    let mut data = read_data(infile)?;
+
+    if args.newline && !data.ends_with(EOL.as_bytes()) {
+        data.extend_from_slice(EOL.as_bytes());
+    }
 
-    if args.newline && !data.ends_with(EOL) {
-        data.push_str(EOL);
+    if args.mode == "all" {
+        return compare_modes(&args, &data);
     }
 
-    let (mut writer, path): (Box<dyn Write>, PathBuf) = match args.outfile {
+    let format = args.format();
+    let (mut writer, path) = open_writer(args.outfile)?;
+
+    let gen_func = generator(&args.mode)?;
+
+    let script = gen_func(&data, args.optimize).render(format);
+
+    if args.optimize {
+        let baseline = gen_func(&data, false).render(format).len();
+        let saved = baseline.saturating_sub(script.len());
+        eprintln!(
+            "Optimized script is {} bytes, {saved} bytes ({:.1}%) smaller \
+             than without --optimize",
+            script.len(),
+            100.0 * saved as f64 / baseline as f64,
+        );
+    }
+
+    if args.verify || args.stats {
+        let stats = Stats::new(&script, &data)?;
+        if args.stats {
+            stats
+                .write(&mut io::stderr())
+                .with_context(|| err_print!())?;
+        }
+        if args.verify {
+            stats.verify()?;
+        }
+    }
+
+    writer
+        .write_all(script.as_bytes())
+        .with_context(|| err_file_write!(path))
+}
+
+fn transform(args: GenerateCli) -> Result<()> {
+    if args.newline || args.optimize || args.verify || args.stats {
+        bail!(
+            "--newline, --optimize, --verify and --stats only apply to modes \
+             that print fixed data"
+        );
+    }
+
+    let eof = Eof::new(&args.eof)?;
+    let script = gen_transform(&args.mode, &args.args, eof)?;
+    let script = script.render(args.format());
+
+    let (mut writer, path) = open_writer(args.outfile)?;
+    writer
+        .write_all(script.as_bytes())
+        .with_context(|| err_file_write!(path))
+}
+
+fn open_writer(outfile: Option<PathBuf>) -> Result<(Box<dyn Write>, PathBuf)> {
+    Ok(match outfile {
         Some(path) => (
             Box::new(
                 File::create(&path).with_context(|| err_file_open!(path))?,
@@ -36,53 +118,82 @@ pub fn subcmd_generate(args: GenerateCli) -> Result<()> {
             path,
         ),
         None => (Box::new(io::stdout()), PathBuf::from("STDOUT")),
-    };
+    })
+}
+
+fn compare_modes(args: &GenerateCli, data: &[u8]) -> Result<()> {
+    if args.outfile.is_some() {
+        bail!("the all mode compares the modes and does not write a script");
+    }
+
+    let modes = GEN_MODES
+        .into_iter()
+        .map(|mode| {
+            let script =
+                generator(mode)?(data, args.optimize).render(args.format());
+            Ok((mode, Stats::new(&script, data)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    write_table(&mut io::stdout(), &modes).with_context(|| err_print!())?;
+
+    if args.verify {
+        for (mode, stats) in &modes {
+            stats.verify().with_context(|| format!("mode: {mode}"))?;
+        }
+    }
+
+    Ok(())
+}
+
+type Generator = fn(&[u8], bool) -> Script;
 
-    let gen_func = match &*args.mode {
+fn generator(mode: &str) -> Result<Generator> {
+    Ok(match mode {
         "charwise" => generator_charwise,
         "linewise" => generator_linewise,
         "unique-chars" => generator_unique_chars,
-        _ => bail!("invalid mode (impossible): {}", args.mode),
-    };
-
-    writer
-        .write_all(gen_func(&data).as_bytes())
-        .with_context(|| err_file_write!(path))
+        "delta" => generator_delta,
+        "best" => generator_best,
+        _ => bail!("invalid mode (impossible): {mode}"),
+    })
 }
 
-fn generator_charwise(data: &str) -> String {
-    let mut script = String::new();
-    gen_loop(&mut script, data.bytes(), true);
-    script.push_str(EOL);
+fn generator_charwise(data: &[u8], optimize: bool) -> Script {
+    let mut script = Script::new();
+    gen_loop(&mut script, data, true, optimize);
     script
 }
 
-fn generator_linewise(data: &str) -> String {
-    let has_final_eol = data.ends_with(EOL);
-    let bf_eol = if data.contains("\r\n") {
-        "+++++++++++++.---.>" // print "\r\n"
-    } else {
-        "++++++++++.>" // print "\n"
-    };
+fn generator_linewise(data: &[u8], optimize: bool) -> Script {
+    // Any "\r" before a "\n" is kept as part of the line so that the data
+    // is reproduced exactly, even with mixed line endings
+    let has_final_eol = data.ends_with(b"\n");
+    let bf_eol = "++++++++++.>"; // print "\n"
 
-    let mut script = String::new();
+    let mut script = Script::new();
 
-    let lines = data.lines().collect::<Vec<_>>();
+    let mut lines = data.split(|&b| b == b'\n').collect::<Vec<_>>();
+    if has_final_eol || data.is_empty() {
+        lines.pop();
+    }
     for (i, line) in lines.iter().enumerate() {
-        gen_loop(&mut script, line.bytes(), true);
+        gen_loop(&mut script, line, true, optimize);
         if i < lines.len() - 1 || has_final_eol {
+            script.comment(format!("prints {}", describe_text(b"\n")));
             script.push_str(bf_eol);
+            script.line();
         }
     }
 
-    script.push_str(EOL);
     script
 }
 
-fn generator_unique_chars(data: &str) -> String {
+fn generator_unique_chars(data: &[u8], optimize: bool) -> Script {
     // Vec of all unique bytes in data, sorted
     let mut unique_data = data
-        .bytes()
+        .iter()
+        .copied()
         .collect::<HashSet<u8>>()
         .into_iter()
         .collect::<Vec<_>>();
@@ -95,73 +206,278 @@ fn generator_unique_chars(data: &str) -> String {
         .map(|(i, &b)| (b, i + 1))
         .collect::<HashMap<u8, usize>>();
 
-    let mut script = String::new();
+    let mut script = Script::new();
 
-    gen_loop(&mut script, unique_data.into_iter(), false);
+    gen_loop(&mut script, &unique_data, false, optimize);
     script.push('>');
     let mut cursor: usize = 1;
 
-    for c in data.bytes() {
-        let cell_index = cell_value_indexes[&c];
+    for segment in data.split_inclusive(|&b| b == b'\n') {
+        script.comment(format!("prints {}", describe_text(segment)));
+        for c in segment {
+            let cell_index = cell_value_indexes[c];
+
+            // Difference between cursor position and next byte's cell index
+            let diff = cell_index as isize - cursor as isize;
+
+            // Code to move the cursor to the target cell and print it
+            let c = if diff < 0 { '<' } else { '>' };
+            script.push_n(c, diff.unsigned_abs());
+            script.push('.');
+
+            cursor = cell_index;
+        }
+        script.line();
+    }
+
+    script
+}
 
-        // Difference between cursor position and next byte's cell index
-        let diff = cell_index as isize - cursor as isize;
+/// The most working cells tried by the delta generator.
+const MAX_DELTA_CELLS: usize = 8;
 
-        // Code to move the cursor to the target cell and print it
-        let c = if diff < 0 { '<' } else { '>' };
-        (0..diff.abs()).for_each(|_| script.push(c));
-        script.push('.');
+fn generator_delta(data: &[u8], optimize: bool) -> Script {
+    (1..=MAX_DELTA_CELLS)
+        .map(|cells| gen_delta(data, cells, optimize))
+        .min_by_key(Script::len)
+        .unwrap_or_default()
+}
+
+/// Print the data from `cells` working cells. Each byte is printed from the
+/// cell that takes the fewest moves and adjustments to reach and set to it.
+fn gen_delta(data: &[u8], cells: usize, optimize: bool) -> Script {
+    // Start the working cells at evenly spaced values of the sorted data
+    let mut sorted = data.to_vec();
+    sorted.sort_unstable();
+    let mut values = (0..cells)
+        .map(|i| {
+            let rank = (2 * i + 1) * sorted.len() / (2 * cells);
+            sorted.get(rank).copied().unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
 
-        cursor = cell_index;
+    let mut script = Script::new();
+    gen_setup(&mut script, &values, optimize);
+
+    // The working cells are to the right of the cursor's starting cell
+    let mut cursor: usize = 0;
+
+    for segment in data.split_inclusive(|&b| b == b'\n') {
+        script.comment(format!("prints {}", describe_text(segment)));
+        for &b in segment {
+            let (cell, diff) = values
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| (i + 1, wrapping_diff(value, b)))
+                .min_by_key(|&(cell, diff)| {
+                    cell.abs_diff(cursor) + diff.unsigned_abs()
+                })
+                .unwrap_or_else(|| unreachable!("there is always a cell"));
+
+            let c = if cell < cursor { '<' } else { '>' };
+            script.push_n(c, cell.abs_diff(cursor));
+            push_diff(&mut script, diff);
+            script.push('.');
+
+            values[cell - 1] = b;
+            cursor = cell;
+        }
+        script.line();
     }
 
-    script.push_str(EOL);
     script
 }
 
+fn generator_best(data: &[u8], optimize: bool) -> Script {
+    let generators: [Generator; 4] = [
+        generator_charwise,
+        generator_linewise,
+        generator_unique_chars,
+        generator_delta,
+    ];
+    generators
+        .into_iter()
+        .map(|gen_func| gen_func(data, optimize))
+        .min_by_key(Script::len)
+        .unwrap_or_default()
+}
+
 fn gen_loop(
-    script: &mut String,
-    data: impl Iterator<Item = u8>,
+    script: &mut Script,
+    values: &[u8],
     print_cells: bool,
+    optimize: bool,
 ) {
-    let values = data.collect::<Vec<_>>();
+    let len = values.len();
+
+    gen_setup(script, values, optimize);
+
+    if print_cells {
+        // Print data
+        script.comment(format!("prints {}", describe_text(values)));
+        (0..len).for_each(|_| script.push_str(">."));
+
+        // Move to empty cell
+        script.push('>');
+        script.line();
+    }
+}
+
+/// Set the cells to the right of the current cell to `values`, ending on the
+/// empty cell to the left of them.
+fn gen_setup(script: &mut Script, values: &[u8], optimize: bool) {
+    if optimize {
+        gen_cells(script, values);
+    } else {
+        gen_cells_fixed(script, values);
+    }
+}
+
+/// Set up the cells with a counter of 10, rounding each value to the nearest
+/// multiple of 10.
+fn gen_cells_fixed(script: &mut Script, values: &[u8]) {
+    // Round to the nearest multiple of 10, staying within the range of a cell
     let approx_values = values
         .iter()
-        .copied()
-        .map(|b| 10 * (b as f32 / 10.0).round() as u8)
+        .map(|&b| (b.saturating_add(5) / 10) * 10)
         .collect::<Vec<_>>();
     let len = values.len();
 
     // Loop counter
     script.push_str("++++++++++[");
+    script.line();
 
     // Increment cells to approximated values
     for approx in &approx_values {
         script.push('>');
-        let factor = *approx / 10;
-        (0..factor).for_each(|_| script.push('+'));
+        script.push_n('+', usize::from(*approx / 10));
+        script.line();
     }
 
     // Return to counter cell and decrement
-    (0..len).for_each(|_| script.push('<'));
+    script.push_n('<', len);
     script.push_str("-]");
+    script.line();
 
     // Adjust cells to real value
-    for (val, approx) in values.iter().zip(&approx_values) {
+    for (i, (val, approx)) in values.iter().zip(&approx_values).enumerate() {
+        script.comment(format!(
+            "cell {} holds {}",
+            i + 1,
+            describe_byte(*val)
+        ));
         script.push('>');
-        let diff = *val as i8 - *approx as i8;
-        let c = if diff < 0 { '-' } else { '+' };
-        (0..diff.abs()).for_each(|_| script.push(c));
+        push_diff(script, isize::from(*val) - isize::from(*approx));
+        script.line();
     }
 
     // Go back to beginning
-    (0..len).for_each(|_| script.push('<'));
+    script.push_n('<', len);
+    script.line();
+}
 
-    if print_cells {
-        // Print data
-        (0..len).for_each(|_| script.push_str(">."));
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
 
-        // Move to empty cell
-        script.push('>');
+    use bf::interpreter::{Interpreter, Options};
+    use proptest::prelude::*;
+
+    use super::{
+        cli::GEN_MODES,
+        generator,
+        script::{describe_byte, describe_text, Format},
+    };
+
+    fn run(script: &str) -> Vec<u8> {
+        let mut int = Interpreter::new(script.bytes(), VecDeque::new(), None);
+        for frame in int.by_ref() {
+            frame.expect("generated script errored");
+        }
+        int.output
+    }
+
+    #[test]
+    fn annotated_scripts_run_in_compat_mode() {
+        // A `!` in a comment would start the input in compat mode
+        assert_eq!(describe_byte(b'!'), "33");
+        assert_eq!(describe_text(b"Hi!"), "\"Hi\\x21\"");
+
+        let data = b"Hello World!\n";
+        let format = Format {
+            annotate: true,
+            ..Format::default()
+        };
+        for mode in GEN_MODES {
+            let script = generator(mode).unwrap()(data, false).render(format);
+            let mut int = Interpreter::with_options(
+                script.bytes(),
+                VecDeque::new(),
+                None,
+                Options::compat(),
+            );
+            for frame in int.by_ref() {
+                frame.expect("generated script errored");
+            }
+            assert_eq!(int.output, data, "mode: {mode}");
+        }
+    }
+
+    fn assert_reproduces(
+        data: &[u8],
+        optimize: bool,
+        format: Format,
+    ) -> Result<(), TestCaseError> {
+        for mode in GEN_MODES {
+            let script = generator(mode).unwrap()(data, optimize);
+            let script = script.render(format);
+            prop_assert_eq!(run(&script), data, "mode: {}", mode);
+        }
+        Ok(())
+    }
+
+    fn any_format() -> impl Strategy<Value = Format> {
+        (
+            prop::option::of(5_usize..40),
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+        )
+            .prop_map(|(wrap, cell_lines, indent, annotate)| Format {
+                wrap,
+                cell_lines,
+                indent,
+                annotate,
+            })
+    }
+
+    proptest! {
+        // Every mode is generated and run for each case, keep this quick
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn generated_scripts_reproduce_binary_data(
+            data in any::<Vec<u8>>(),
+            optimize in any::<bool>(),
+        ) {
+            assert_reproduces(&data, optimize, Format::default())?;
+        }
+
+        #[test]
+        fn generated_scripts_reproduce_text(
+            text in "[ -~\\t\\r\\n]{0,64}",
+            optimize in any::<bool>(),
+        ) {
+            assert_reproduces(text.as_bytes(), optimize, Format::default())?;
+        }
+
+        #[test]
+        fn formatted_scripts_reproduce_binary_data(
+            data in any::<Vec<u8>>(),
+            optimize in any::<bool>(),
+            format in any_format(),
+        ) {
+            assert_reproduces(&data, optimize, format)?;
+        }
     }
 }
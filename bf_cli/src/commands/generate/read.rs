@@ -1,20 +1,20 @@
 use std::{
-    fs::File,
+    fs,
     io::{self, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Result};
 
-use crate::{err_file_open, err_file_read};
+use crate::err_file_read;
 
-pub fn read_data(infile: Option<PathBuf>) -> Result<String> {
-    infile.map_or_else(read_data_stdin, |path| read_data_file(&path))
+pub fn read_data(infile: Option<&Path>) -> Result<Vec<u8>> {
+    infile.map_or_else(read_data_stdin, read_data_file)
 }
 
-fn read_data_stdin() -> Result<String> {
-    let mut data = String::new();
-    match io::stdin().read_to_string(&mut data) {
+fn read_data_stdin() -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    match io::stdin().read_to_end(&mut data) {
         Ok(_) => Ok(data),
         Err(e) => {
             Err(e).with_context(|| err_file_read!(PathBuf::from("STDIN")))
@@ -22,11 +22,6 @@ fn read_data_stdin() -> Result<String> {
     }
 }
 
-fn read_data_file(path: &PathBuf) -> Result<String> {
-    let mut file = File::open(path).with_context(|| err_file_open!(path))?;
-    let mut data = String::new();
-    match file.read_to_string(&mut data) {
-        Ok(_) => Ok(data),
-        Err(e) => Err(e).with_context(|| err_file_read!(path)),
-    }
+fn read_data_file(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| err_file_read!(path))
 }
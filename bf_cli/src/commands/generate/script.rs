@@ -0,0 +1,196 @@
+use std::iter;
+
+use bf::interpreter::Interpreter;
+
+use super::EOL;
+
+/// How a generated script is laid out.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Format {
+    /// Wrap lines of instructions at this many columns.
+    pub wrap: Option<usize>,
+    /// Start a new line for the setup of each cell and each print block.
+    pub cell_lines: bool,
+    /// Put loop bodies on their own lines, indented by the loop depth.
+    pub indent: bool,
+    /// Include the comments describing the cells and the printed text.
+    pub annotate: bool,
+}
+
+const INDENT: &str = "  ";
+
+enum Mark {
+    Break,
+    Comment(String),
+}
+
+/// A generated script: the instructions, along with where lines may be
+/// broken and what comments may be added when it is formatted.
+#[derive(Default)]
+pub struct Script {
+    code: String,
+    marks: Vec<(usize, Mark)>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of instructions in the script.
+    pub const fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.code.push(c);
+    }
+
+    pub fn push_str(&mut self, code: &str) {
+        self.code.push_str(code);
+    }
+
+    pub fn push_n(&mut self, c: char, n: usize) {
+        self.code.extend(iter::repeat_n(c, n));
+    }
+
+    /// Mark the end of a section, such as the setup of one cell.
+    pub fn line(&mut self) {
+        self.marks.push((self.code.len(), Mark::Break));
+    }
+
+    /// Add a comment before the next instructions. It must not contain any
+    /// Brainfuck instructions, or the `!` that starts the input in compat
+    /// mode, see [`describe_byte`] and [`describe_text`].
+    pub fn comment(&mut self, comment: String) {
+        debug_assert!(comment.bytes().all(is_plain));
+        self.marks.push((self.code.len(), Mark::Comment(comment)));
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        let mut writer = LineWriter {
+            out: String::with_capacity(self.code.len() + EOL.len()),
+            column: 0,
+            depth: 0,
+            format,
+        };
+
+        let mut marks = self.marks.iter().peekable();
+        for (i, c) in self.code.char_indices() {
+            while let Some((_, mark)) = marks.next_if(|(at, _)| *at <= i) {
+                writer.mark(mark);
+            }
+            writer.instruction(c);
+        }
+        for (_, mark) in marks {
+            writer.mark(mark);
+        }
+
+        if writer.column > 0 || writer.out.is_empty() {
+            writer.out.push_str(EOL);
+        }
+        writer.out
+    }
+}
+
+struct LineWriter {
+    out: String,
+    column: usize,
+    depth: usize,
+    format: Format,
+}
+
+impl LineWriter {
+    fn end_line(&mut self) {
+        if self.column > 0 {
+            self.out.push_str(EOL);
+            self.column = 0;
+        }
+    }
+
+    fn start_line(&mut self) {
+        if self.column == 0 && self.format.indent {
+            for _ in 0..self.depth {
+                self.out.push_str(INDENT);
+            }
+            self.column = self.depth * INDENT.len();
+        }
+    }
+
+    fn mark(&mut self, mark: &Mark) {
+        match mark {
+            Mark::Break if self.format.cell_lines => self.end_line(),
+            Mark::Comment(comment) if self.format.annotate => {
+                self.end_line();
+                self.start_line();
+                self.out.push_str(comment);
+                self.column += comment.len();
+                self.end_line();
+            }
+            _ => {}
+        }
+    }
+
+    fn instruction(&mut self, c: char) {
+        let indent = self.format.indent;
+
+        if c == ']' && indent {
+            self.end_line();
+            self.depth = self.depth.saturating_sub(1);
+        }
+
+        if let Some(wrap) = self.format.wrap {
+            if self.column >= wrap.max(self.depth * INDENT.len() + 1) {
+                self.end_line();
+            }
+        }
+
+        self.start_line();
+        self.out.push(c);
+        self.column += 1;
+
+        if c == '[' && indent {
+            self.depth += 1;
+            self.end_line();
+        } else if c == ']' && indent {
+            self.end_line();
+        }
+    }
+}
+
+/// Describe a byte for a comment, showing the character if it is printable
+/// and neither a Brainfuck instruction nor `!`.
+pub fn describe_byte(b: u8) -> String {
+    if is_plain(b) {
+        format!("{b} '{}'", char::from(b))
+    } else {
+        b.to_string()
+    }
+}
+
+/// Quote text for a comment, escaping any bytes that are not printable or
+/// are Brainfuck instructions or `!`.
+pub fn describe_text(text: &[u8]) -> String {
+    let mut desc = String::from("\"");
+    for &b in text {
+        match b {
+            b'\n' => desc.push_str("\\n"),
+            b'\r' => desc.push_str("\\r"),
+            b'\t' => desc.push_str("\\t"),
+            b if is_plain(b) && b != b'"' && b != b'\\' => {
+                desc.push(char::from(b));
+            }
+            b => desc.push_str(&format!("\\x{b:02x}")),
+        }
+    }
+    desc.push('"');
+    desc
+}
+
+/// Whether the byte may be written as is in a comment. `!` is left out
+/// since compat mode reads the rest of the script as input after it.
+const fn is_plain(b: u8) -> bool {
+    (b.is_ascii_graphic() || b == b' ')
+        && !Interpreter::is_instruction(b)
+        && b != b'!'
+}
@@ -0,0 +1,97 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+};
+
+use anyhow::{bail, Result};
+use bf::interpreter::Interpreter;
+
+/// Statistics about a generated script and its execution.
+pub struct Stats {
+    pub length: usize,
+    pub instructions: usize,
+    pub steps: u64,
+    pub cells: usize,
+    /// The offset of the first byte of output that differs from the data,
+    /// if any.
+    pub mismatch: Option<usize>,
+}
+
+impl Stats {
+    /// Execute the script and compare its output to `data`.
+    pub fn new(script: &str, data: &[u8]) -> Result<Self> {
+        let mut int = Interpreter::new(script.bytes(), VecDeque::new(), None);
+        let mut steps = 0;
+        for frame in int.by_ref() {
+            if let Err(err) = frame {
+                bail!("the generated script failed: {err}");
+            }
+            steps += 1;
+        }
+
+        let output = int.output_bytes();
+        let mismatch = (output != data).then(|| {
+            output
+                .iter()
+                .zip(data)
+                .position(|(a, b)| a != b)
+                .unwrap_or_else(|| output.len().min(data.len()))
+        });
+
+        Ok(Self {
+            length: script.len(),
+            instructions: script
+                .bytes()
+                .filter(|&b| Interpreter::is_instruction(b))
+                .count(),
+            steps,
+            cells: int.tape.len(),
+            mismatch,
+        })
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        match self.mismatch {
+            Some(offset) => bail!(
+                "verification failed: the output of the generated script \
+                 differs from the data at byte {offset}"
+            ),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Script length: {} bytes", self.length)?;
+        writeln!(out, "Instructions:  {}", self.instructions)?;
+        writeln!(out, "Steps:         {}", self.steps)?;
+        writeln!(out, "Tape cells:    {}", self.cells)
+    }
+}
+
+/// Write a table comparing the scripts generated by each mode.
+pub fn write_table(
+    out: &mut impl Write,
+    modes: &[(&str, Stats)],
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:<12}  {:>10}  {:>12}  {:>12}  {:>6}  Verified",
+        "Mode", "Length", "Instructions", "Steps", "Cells"
+    )?;
+    for (mode, stats) in modes {
+        writeln!(
+            out,
+            "{mode:<12}  {:>10}  {:>12}  {:>12}  {:>6}  {}",
+            stats.length,
+            stats.instructions,
+            stats.steps,
+            stats.cells,
+            if stats.mismatch.is_none() {
+                "yes"
+            } else {
+                "NO"
+            },
+        )?;
+    }
+    Ok(())
+}
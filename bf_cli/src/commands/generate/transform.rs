@@ -0,0 +1,445 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use anyhow::{bail, Result};
+
+use crate::utils::input::parse_input;
+
+use super::{constants::wrapping_diff, script::Script};
+
+pub const TRANSFORM_MODES: [&str; 7] =
+    ["cat", "rev", "upper", "lower", "rot13", "count-bytes", "tr"];
+
+pub const EOF_CONVENTIONS: [&str; 3] = ["zero", "minus-one", "unchanged"];
+
+/// The number of decimal digits kept by the `count-bytes` counter.
+const COUNT_DIGITS: usize = 10;
+
+/// How the interpreter running the generated script reports the end of
+/// input to `,`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eof {
+    /// The cell is set to 0.
+    Zero,
+    /// The cell is set to 255.
+    MinusOne,
+    /// The cell is left as it was.
+    Unchanged,
+}
+
+impl Eof {
+    pub fn new(convention: &str) -> Result<Self> {
+        Ok(match convention {
+            "zero" => Self::Zero,
+            "minus-one" => Self::MinusOne,
+            "unchanged" => Self::Unchanged,
+            _ => bail!("invalid EOF convention (impossible): {convention}"),
+        })
+    }
+
+    /// Read a byte into the current cell, leaving it 0 at the end of input.
+    const fn read(self) -> &'static str {
+        match self {
+            Self::Zero => ",",
+            Self::MinusOne => ",+",
+            Self::Unchanged => "[-],",
+        }
+    }
+
+    /// Loop over each byte of input, read into the current cell. The body
+    /// starts with the byte in the current cell and must end on the cell
+    /// that the next byte is read into.
+    fn read_loop(self, script: &mut Script, body: impl FnOnce(&mut Script)) {
+        script.push_str(self.read());
+        script.push('[');
+        if self == Self::MinusOne {
+            script.push('-');
+        }
+        body(script);
+        script.push_str(self.read());
+        script.push(']');
+    }
+}
+
+/// Build the script for a transform mode. `args` are the FROM and TO sets of
+/// the `tr` mode and must be empty for every other mode.
+pub fn gen_transform(mode: &str, args: &[String], eof: Eof) -> Result<Script> {
+    if mode != "tr" && !args.is_empty() {
+        bail!("the {mode} mode reads its input when run and takes no file");
+    }
+
+    let mut script = Script::new();
+    match mode {
+        "cat" => gen_cat(&mut script, eof),
+        "rev" => gen_rev(&mut script, eof),
+        "upper" => gen_map(&mut script, eof, &case_map(b'a'..=b'z', b'A')),
+        "lower" => gen_map(&mut script, eof, &case_map(b'A'..=b'Z', b'a')),
+        "rot13" => gen_map(&mut script, eof, &rot13_map()),
+        "count-bytes" => gen_count(&mut script, eof),
+        "tr" => {
+            let [from, to] = args else {
+                bail!("the tr mode takes exactly two arguments: FROM TO");
+            };
+            gen_map(&mut script, eof, &tr_map(from, to)?);
+        }
+        _ => bail!("invalid mode (impossible): {mode}"),
+    }
+    Ok(script)
+}
+
+fn gen_cat(script: &mut Script, eof: Eof) {
+    script.comment("print each byte of input as it is read".to_string());
+    eof.read_loop(script, |script| script.push('.'));
+}
+
+fn gen_rev(script: &mut Script, eof: Eof) {
+    // Each byte is stored in a pair of cells: a flag that is set once the
+    // byte is read, then the byte itself. The flags mark where the input
+    // starts even if it contains null bytes.
+    script.comment("store the input with a flag before each byte".to_string());
+    script.push_str(">>>");
+    eof.read_loop(script, |script| script.push_str("<+>>>"));
+    script.line();
+
+    script.comment("print the bytes back to front".to_string());
+    script.push_str("<<<[>.<<<]");
+}
+
+/// Map each byte of `range` to the bytes starting at `to`.
+fn case_map(range: impl Iterator<Item = u8>, to: u8) -> BTreeMap<u8, u8> {
+    range.zip(to..).collect()
+}
+
+fn rot13_map() -> BTreeMap<u8, u8> {
+    b"aA"
+        .iter()
+        .flat_map(|base| {
+            (0..26).map(move |i| (base + i, base + (i + 13) % 26))
+        })
+        .collect()
+}
+
+/// The translation of `tr FROM TO`: each byte of FROM becomes the byte at
+/// the same position in TO, or the last byte of TO if TO is shorter. When a
+/// byte is repeated in FROM, its last position wins.
+fn tr_map(from: &str, to: &str) -> Result<BTreeMap<u8, u8>> {
+    let from = parse_set(from)?;
+    let to = parse_set(to)?;
+    let Some(&last) = to.last() else {
+        bail!("the TO set of the tr mode must not be empty");
+    };
+
+    Ok(from
+        .into_iter()
+        .zip(to.into_iter().chain(std::iter::repeat(last)))
+        .collect())
+}
+
+/// Parse a set of bytes for the tr mode: escape sequences as in the escaped
+/// input format, and ranges such as `a-z`. A `-` at either end is itself.
+fn parse_set(set: &str) -> Result<Vec<u8>> {
+    let bytes = parse_input(set, "escaped")?;
+    let mut out = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if i + 2 < bytes.len() && bytes[i + 1] == b'-' {
+            let (start, end) = (bytes[i], bytes[i + 2]);
+            if start > end {
+                bail!(
+                    "range {}-{} in a tr set is in reverse order",
+                    char::from(start),
+                    char::from(end)
+                );
+            }
+            out.extend(start..=end);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Print each byte of input through a translation table. Bytes missing
+/// from the table are printed as they are.
+///
+/// The cells are `[byte, counter, flag]`. The counter is a copy of the byte
+/// which is decremented through nested loops, one per byte value up to the
+/// largest byte in the table. The loops stop at the value of the byte, where
+/// the flag is still set, and the byte is changed to its translation.
+fn gen_map(script: &mut Script, eof: Eof, map: &BTreeMap<u8, u8>) {
+    let map = map
+        .iter()
+        .filter(|(from, to)| from != to)
+        .map(|(&from, &to)| (from, to))
+        .collect::<BTreeMap<_, _>>();
+
+    script.comment("translate each byte of input then print it".to_string());
+    eof.read_loop(script, |script| {
+        let Some(&max) = map.keys().next_back() else {
+            script.push('.');
+            return;
+        };
+
+        // Copy the byte to the counter, using the flag as a temporary
+        script.push_str("[->+>+<<]>>[-<<+>>]+<");
+        script.line();
+
+        for _ in 0..=max {
+            script.push_str("[-");
+        }
+        // Above the largest byte in the table: leave the byte alone
+        script.push_str("[-]>-<");
+        for value in (0..=max).rev() {
+            script.push(']');
+            script.push_str(">[-");
+            if let Some(&to) = map.get(&value) {
+                let diff = wrapping_diff(value, to);
+                let c = if diff < 0 { '-' } else { '+' };
+                script.push_str("<<");
+                script.push_n(c, diff.unsigned_abs());
+                script.push_str(">>");
+            }
+            script.push_str("]<");
+        }
+        script.line();
+
+        script.push_str("<.");
+    });
+}
+
+/// Move between two cells.
+fn push_move(script: &mut Script, from: usize, to: usize) {
+    match to.cmp(&from) {
+        Ordering::Greater => script.push_n('>', to - from),
+        Ordering::Less => script.push_n('<', from - to),
+        Ordering::Equal => {}
+    }
+}
+
+/// The cells of the `count-bytes` script, after the byte read from input.
+const TEMP: usize = 1;
+const FLAG: usize = 2;
+const DIGITS: usize = 3;
+
+/// Count the bytes of input, then print the count in decimal.
+///
+/// The count is kept as one cell per decimal digit, least significant
+/// first, so it can be printed without any division.
+fn gen_count(script: &mut Script, eof: Eof) {
+    script.comment("count each byte of input".to_string());
+    eof.read_loop(script, |script| {
+        script.line();
+        gen_increment(script, 0);
+        script.line();
+    });
+    script.line();
+
+    // The cell that the input was read into is 0 at the end of input and
+    // is reused to remember if a nonzero digit has been printed yet
+    script.comment("print the count without leading zeros".to_string());
+    for digit in (0..COUNT_DIGITS).rev() {
+        let cell = DIGITS + digit;
+        if digit > 0 {
+            // Set the started flag if the digit is nonzero
+            push_copy(script, cell, TEMP);
+            push_move(script, 0, TEMP);
+            script.push_str("[[-]");
+            push_move(script, TEMP, 0);
+            script.push_str("[-]+");
+            push_move(script, 0, TEMP);
+            script.push(']');
+            push_move(script, TEMP, 0);
+
+            // Print the digit if the started flag is set
+            push_copy(script, 0, TEMP);
+            push_move(script, 0, TEMP);
+            script.push_str("[-");
+            push_move(script, TEMP, cell);
+        } else {
+            push_move(script, 0, cell);
+        }
+        script.push_n('+', 48);
+        script.push('.');
+        script.push_n('-', 48);
+        if digit > 0 {
+            push_move(script, cell, TEMP);
+            script.push(']');
+            push_move(script, TEMP, 0);
+        } else {
+            push_move(script, cell, 0);
+        }
+        script.line();
+    }
+
+    script.push_str(">++++++++++.");
+}
+
+/// Copy cell `from` to cell `to`, using the flag as a temporary. Starts and
+/// ends on cell 0. `to` must be 0 and must not be the flag.
+fn push_copy(script: &mut Script, from: usize, to: usize) {
+    push_move(script, 0, from);
+    script.push_str("[-");
+    push_move(script, from, to);
+    script.push('+');
+    push_move(script, to, FLAG);
+    script.push('+');
+    push_move(script, FLAG, from);
+    script.push(']');
+    push_move(script, from, FLAG);
+    script.push_str("[-");
+    push_move(script, FLAG, from);
+    script.push('+');
+    push_move(script, from, FLAG);
+    script.push(']');
+    push_move(script, FLAG, 0);
+}
+
+/// Add one to the digit, carrying into the next digit when it reaches 10.
+/// Starts and ends on cell 0.
+fn gen_increment(script: &mut Script, digit: usize) {
+    let cell = DIGITS + digit;
+    push_move(script, 0, cell);
+    script.push('+');
+    push_move(script, cell, 0);
+    if digit + 1 == COUNT_DIGITS {
+        return;
+    }
+
+    // The flag is set when the digit is 10, found by subtracting 10 from a
+    // copy of it
+    push_copy(script, cell, TEMP);
+    push_move(script, 0, FLAG);
+    script.push('+');
+    push_move(script, FLAG, TEMP);
+    script.push_n('-', 10);
+    script.push_str("[[-]>-<]>");
+
+    script.push_str("[-");
+    push_move(script, FLAG, cell);
+    script.push_str("[-]");
+    push_move(script, cell, 0);
+    gen_increment(script, digit + 1);
+    push_move(script, 0, FLAG);
+    script.push(']');
+    push_move(script, FLAG, 0);
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use bf::interpreter::Interpreter;
+    use proptest::prelude::*;
+
+    use super::{gen_transform, Eof, EOF_CONVENTIONS};
+    use crate::commands::generate::script::Format;
+
+    fn run(script: &str, input: &[u8], eof: Eof) -> Vec<u8> {
+        let eof_value = if eof == Eof::MinusOne { 255 } else { 0 };
+        let input = VecDeque::from(input.to_vec());
+        let mut int = Interpreter::new(script.bytes(), input, Some(eof_value));
+        for frame in int.by_ref() {
+            frame.expect("generated script errored");
+        }
+        int.output
+    }
+
+    fn transform(
+        mode: &str,
+        args: &[&str],
+        input: &[u8],
+        eof: Eof,
+    ) -> Vec<u8> {
+        let args = args.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let script = gen_transform(mode, &args, eof).unwrap();
+        run(&script.render(Format::default()), input, eof)
+    }
+
+    /// Input that does not contain the byte used to signal the end of it.
+    fn any_input() -> impl Strategy<Value = (Vec<u8>, Eof)> {
+        prop::sample::select(EOF_CONVENTIONS.to_vec()).prop_flat_map(|eof| {
+            let eof = Eof::new(eof).unwrap();
+            let bytes = if eof == Eof::MinusOne {
+                0..=254_u8
+            } else {
+                1..=255
+            };
+            (prop::collection::vec(bytes, 0..64), Just(eof))
+        })
+    }
+
+    fn rot13(b: u8) -> u8 {
+        match b {
+            b'a'..=b'z' => (b - b'a' + 13) % 26 + b'a',
+            b'A'..=b'Z' => (b - b'A' + 13) % 26 + b'A',
+            _ => b,
+        }
+    }
+
+    #[test]
+    fn count_bytes_carries() {
+        for len in [0, 9, 10, 99, 100, 1234] {
+            for eof in EOF_CONVENTIONS {
+                let eof = Eof::new(eof).unwrap();
+                let out = transform("count-bytes", &[], &vec![b'x'; len], eof);
+                assert_eq!(out, format!("{len}\n").into_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn tr_sets() {
+        let eof = Eof::Zero;
+        let out = transform("tr", &["a-c\\n", "x-"], b"abcd\n", eof);
+        assert_eq!(out, b"x--d-");
+        let out = transform("tr", &["-a", "b\\x2d"], b"a-", eof);
+        assert_eq!(out, b"-b");
+        let fails = |mode: &str, args: &[&str]| {
+            let args =
+                args.iter().map(ToString::to_string).collect::<Vec<_>>();
+            gen_transform(mode, &args, eof).is_err()
+        };
+        assert!(fails("tr", &["a", ""]));
+        assert!(fails("tr", &["z-a", "b"]));
+        assert!(fails("tr", &["a"]));
+        assert!(fails("cat", &["a"]));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn transforms_match_reference((input, eof) in any_input()) {
+            let rev = input.iter().rev().copied().collect::<Vec<_>>();
+            let upper = input.to_ascii_uppercase();
+            let lower = input.to_ascii_lowercase();
+            let rot13 = input.iter().copied().map(rot13).collect::<Vec<_>>();
+            let count = format!("{}\n", input.len()).into_bytes();
+            let tr = input
+                .iter()
+                .map(|&b| match b {
+                    b'a'..=b'e' => b + 20,
+                    b'0' => b'!',
+                    _ => b,
+                })
+                .collect::<Vec<_>>();
+
+            prop_assert_eq!(transform("cat", &[], &input, eof), &*input);
+            prop_assert_eq!(transform("rev", &[], &input, eof), rev);
+            prop_assert_eq!(transform("upper", &[], &input, eof), upper);
+            prop_assert_eq!(transform("lower", &[], &input, eof), lower);
+            prop_assert_eq!(transform("rot13", &[], &input, eof), rot13);
+            prop_assert_eq!(
+                transform("count-bytes", &[], &input, eof),
+                count
+            );
+            prop_assert_eq!(
+                transform("tr", &["a-e0", "u-y!"], &input, eof),
+                tr
+            );
+        }
+    }
+}
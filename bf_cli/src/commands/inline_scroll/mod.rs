@@ -37,10 +37,11 @@ macro_rules! event_matches {
 }
 
 fn run(args: InlineScrollCli) -> Result<()> {
-    let script: String = read_script(args.infile.as_ref())?
-        .iter()
-        .flat_map(|l| l.chars().filter(|c| !c.is_whitespace()))
-        .collect();
+    let script: String =
+        String::from_utf8_lossy(&read_script(args.infile.as_ref())?)
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
 
     let mut output: Vec<String> = Vec::default();
     let mut id = 0;
@@ -56,7 +57,7 @@ fn run(args: InlineScrollCli) -> Result<()> {
     let event_queue = EventQueue::with_ticks(args.delay);
     let mut quit = false;
 
-    let ret = try {
+    let mut run_loop = || -> Result<()> {
         while !quit {
             for event in event_queue.pop_all() {
                 match event {
@@ -84,7 +85,9 @@ fn run(args: InlineScrollCli) -> Result<()> {
                 }
             }
         }
+        Ok(())
     };
+    let ret = run_loop();
 
     disable_raw_mode()?;
     println!();
@@ -37,7 +37,7 @@ impl State {
         }
     }
 
-    pub fn get_input_history(&self) -> vec_deque::Iter<InputHistoryEntry> {
+    pub fn get_input_history(&self) -> vec_deque::Iter<'_, InputHistoryEntry> {
         self.input_history.iter()
     }
 
@@ -45,7 +45,7 @@ impl State {
         self.spinner
     }
 
-    pub fn spinner_tick(&mut self) {
+    pub const fn spinner_tick(&mut self) {
         self.spinner.tick();
     }
 
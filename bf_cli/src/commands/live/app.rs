@@ -15,9 +15,10 @@ use bf_tui::{
     events::{BfEvent, EventQueue, KeyEventExt},
     widgets::{
         live::{
-            AppWidget, Dialog, DialogCommand, ErrorDialog, FileSaveAsDialog,
-            ScriptAutoInputDialog, ScriptInputDialog, TapeViewportState,
-            TextAreaExts, UnsavedChangesDialog,
+            AppWidget, Dialog, DialogCommand, Editor, EditorState,
+            ErrorDialog, FileSaveAsDialog, Palette, ScriptAutoInputDialog,
+            ScriptInputDialog, TapeViewportState, TextAreaExts,
+            UnsavedChangesDialog,
         },
         Spinner,
     },
@@ -34,14 +35,15 @@ use crossterm::{
         LeaveAlternateScreen,
     },
 };
-use ratatui::{
-    backend::CrosstermBackend,
-    style::{Color, Style},
-};
+use ratatui::backend::CrosstermBackend;
 use tracing::{trace, warn};
 use tui_textarea::TextArea;
 
-use crate::utils::read::read_script_file;
+use crate::utils::{
+    dialect::RunDialect,
+    palette::load_palette,
+    read::{read_script_file, script_lines},
+};
 
 use super::{cli::LiveCli, logging::init_logging};
 
@@ -59,6 +61,11 @@ fn set_panic_hook() {
     }));
 }
 
+/// The script in the editor, translated to Brainfuck.
+fn script_code(dialect: &RunDialect, code: &TextArea) -> Vec<u8> {
+    dialect.decode(code.bytes().collect())
+}
+
 pub struct App<'code, 'dialog> {
     term_width: usize,
     term_height: usize,
@@ -67,9 +74,14 @@ pub struct App<'code, 'dialog> {
     should_quit: bool,
     spinner: Spinner,
     code: TextArea<'code>,
+    editor_state: EditorState,
+    palette: Palette,
     tape_viewport: TapeViewportState,
     input: String,
     auto_input: Option<u8>,
+    dialect: RunDialect,
+    /// Whether the input of the script is embedded after a `!`.
+    embedded_input: bool,
     clean_hash: Sha1Digest,
     event_queue: EventQueue,
     delay: Duration,
@@ -94,7 +106,7 @@ impl<'code, 'dialog> App<'code, 'dialog> {
         execute!(stdout(), EnableMouseCapture, EnterAlternateScreen)?;
 
         let script = if let Some(path) = &cli.infile {
-            read_script_file(path)?
+            script_lines(&read_script_file(path)?)
         } else {
             Vec::new()
         };
@@ -105,11 +117,21 @@ impl<'code, 'dialog> App<'code, 'dialog> {
             .copied()
             .collect::<Vec<_>>();
 
-        let mut code = TextArea::from(script);
-        code.set_line_number_style(Style::default().fg(Color::Yellow));
-        code.set_cursor_line_style(Style::default());
+        let code = TextArea::from(script);
 
-        let interpreter_code = code.bytes().collect();
+        let dialect = RunDialect::load(&cli.dialect)?;
+        let mut palette = match &cli.palette {
+            Some(path) => load_palette(path)?,
+            None => Palette::default(),
+        };
+        if dialect.has_tokens() {
+            palette = Palette {
+                line_numbers: palette.line_numbers,
+                ..Palette::plain()
+            };
+        }
+        let interpreter_code = script_code(&dialect, &code);
+        let options = dialect.options(cli.compat);
 
         let mut this = Self {
             term_width: 0,
@@ -119,9 +141,13 @@ impl<'code, 'dialog> App<'code, 'dialog> {
             should_quit: false,
             spinner: Spinner::default(),
             code,
+            editor_state: EditorState::default(),
+            palette,
             tape_viewport: TapeViewportState::new(cli.ascii_values),
             input: String::new(),
             auto_input: None,
+            dialect,
+            embedded_input: options.embedded_input,
             clean_hash: sha1_digest(script_raw),
             event_queue: EventQueue::with_ticks(100),
             delay: Duration::from_millis(20),
@@ -130,6 +156,7 @@ impl<'code, 'dialog> App<'code, 'dialog> {
                 interpreter_code,
                 VecDeque::default(),
                 None,
+                options,
             ),
         };
 
@@ -206,7 +233,7 @@ impl<'code, 'dialog> App<'code, 'dialog> {
                     self.dialog = Some(ErrorDialog::build(fe));
                 }
                 self.async_interpreter.restart(
-                    self.code.bytes().collect(),
+                    script_code(&self.dialect, &self.code),
                     self.input.bytes().collect(),
                     self.auto_input,
                 )?;
@@ -219,13 +246,20 @@ impl<'code, 'dialog> App<'code, 'dialog> {
         Ok(())
     }
 
-    fn draw(&self, terminal: &mut Terminal) -> Result<()> {
+    fn draw(&mut self, terminal: &mut Terminal) -> Result<()> {
         let widget = AppWidget {
             is_dirty: self.is_dirty(),
             async_interpreter: self.async_interpreter.state(),
-            editor: &self.code,
+            editor: Editor::new(
+                &self.code,
+                &self.palette,
+                self.dialect.extension,
+            )
+            .match_brackets(!self.dialect.has_tokens())
+            .embedded_input(self.embedded_input),
+            editor_state: &mut self.editor_state,
             dialog: self.dialog.as_ref(),
-            file_path: self.get_file_path(),
+            file_path: self.file_path.as_deref(),
             spinner: self.spinner,
             tape_viewport: self.tape_viewport,
             term_height: self.term_height,
@@ -270,6 +304,10 @@ impl<'code, 'dialog> App<'code, 'dialog> {
                     's' => self.on_save(),
                     'x' => self.on_save_as(),
                     'a' => self.tape_viewport.ascii_values ^= true,
+                    'b' if !self.dialect.has_tokens() => {
+                        let embedded_input = self.embedded_input;
+                        self.code.jump_to_matching_bracket(embedded_input);
+                    }
                     'c' => self.on_exit(),
                     _ => (),
                 },
@@ -380,3 +418,41 @@ impl<'code, 'dialog> App<'code, 'dialog> {
         self.dialog = Some(ScriptAutoInputDialog::build());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bf::interpreter::Interpreter;
+    use tui_textarea::TextArea;
+
+    use crate::utils::dialect::RunDialect;
+
+    use super::script_code;
+
+    fn run(dialect: &str, lines: &[&str], compat: bool) -> Vec<u8> {
+        let dialect = RunDialect::load(dialect).unwrap();
+        let code = script_code(&dialect, &TextArea::from(lines.to_vec()));
+        let mut int = Interpreter::with_options(
+            code.into_iter(),
+            [].into(),
+            Some(0),
+            dialect.options(compat),
+        );
+        for frame in int.by_ref() {
+            frame.unwrap();
+        }
+        int.output
+    }
+
+    #[test]
+    fn embedded_input_keeps_its_lines() {
+        let output = run("brainfuck", &[",[.,]!a", "b"], true);
+        assert_eq!(output, b"a\nb\n");
+    }
+
+    #[test]
+    fn tokens_may_be_split_across_lines() {
+        // `+.` in Ook!, with a line break inside each token
+        let output = run("ook", &["Ook.", "Ook. Ook!", "Ook."], false);
+        assert_eq!(output, [1]);
+    }
+}
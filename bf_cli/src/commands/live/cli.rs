@@ -3,7 +3,13 @@ use std::path::PathBuf;
 use anyhow::Result;
 use clap::Parser;
 
-use crate::cli::{parse_infile, SubCmd};
+use crate::{
+    cli::{parse_infile, SubCmd},
+    utils::{
+        dialect::{parse_run_dialect, COMPAT_HELP, RUN_DIALECT_HELP},
+        palette::PALETTE_HELP,
+    },
+};
 
 use super::app::App;
 
@@ -18,6 +24,20 @@ pub struct LiveCli {
     #[arg(short, long, help=ASCII_HELP)]
     pub ascii_values: bool,
 
+    #[arg(
+        long,
+        default_value="brainfuck",
+        value_parser=parse_run_dialect,
+        help=RUN_DIALECT_HELP
+    )]
+    pub dialect: String,
+
+    #[arg(long, help=COMPAT_HELP)]
+    pub compat: bool,
+
+    #[arg(long, help=PALETTE_HELP)]
+    pub palette: Option<PathBuf>,
+
     #[arg(value_parser=parse_infile, help=INFILE_HELP)]
     pub infile: Option<PathBuf>,
 }
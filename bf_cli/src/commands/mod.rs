@@ -1,3 +1,11 @@
+pub mod asm;
+
+pub mod batch;
+
+pub mod cover;
+
+pub mod equiv;
+
 pub mod generate;
 
 pub mod inline_scroll;
@@ -7,3 +15,7 @@ pub mod input_debug;
 pub mod live;
 
 pub mod run;
+
+pub mod trace_view;
+
+pub mod translate;
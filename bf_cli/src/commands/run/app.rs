@@ -1,23 +1,37 @@
 use std::{
-    fs::File,
-    io::{stdout, Write},
+    collections::VecDeque,
+    io::{self, stdout, IsTerminal, Read},
     path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
 };
 
-use anyhow::{Context, Error, Result};
-use bf::interpreter::Interpreter;
+use anyhow::{Error, Result};
+use bf::interpreter::{Interpreter, Schedule};
 use bf_tui::{
     events::{BfEvent, EventQueue, KeyEventExt},
-    widgets::run::{AppWidget, AppWidgetState},
+    widgets::run::{AppWidget, AppWidgetState, PlaybackStatus},
     Terminal,
 };
 use crossterm::{
-    event::{Event, KeyCode},
+    event::{Event, KeyCode, KeyEvent},
     terminal::{disable_raw_mode, enable_raw_mode},
 };
 use ratatui::prelude::CrosstermBackend;
 
-use crate::{err_file_open, err_file_write, utils::read::read_script};
+use super::{
+    current_cell, load_input, load_script, new_interpreter,
+    report::write_report, schedule, trace::Tracer, write_outfile, Outcome,
+};
+
+/// The most instructions executed per tick when running to the next output
+/// byte, so that the UI stays responsive during long stretches without
+/// output.
+const STEPS_PER_TICK: usize = 10_000;
+
+/// The slowest tick delay, in milliseconds, reachable with `-`.
+const MAX_DELAY: u64 = 2000;
 
 fn reset_terminal() {
     disable_raw_mode().ok();
@@ -31,12 +45,92 @@ fn set_panic_hook() {
     }));
 }
 
+/// The input typed by the user while the script runs.
+#[derive(Default)]
+struct TypedInput {
+    /// Every byte typed so far, as shown to the user.
+    echo: Vec<u8>,
+    /// The number of bytes added by each key, so that backspace removes
+    /// whole characters.
+    key_lens: Vec<usize>,
+}
+
+impl TypedInput {
+    fn push(&mut self, queue: &mut VecDeque<u8>, bytes: &[u8]) {
+        queue.extend(bytes);
+        self.echo.extend_from_slice(bytes);
+        self.key_lens.push(bytes.len());
+    }
+
+    /// Remove the last key from the input `queue` of the script, unless the
+    /// script has already read some of it.
+    fn backspace(&mut self, queue: &mut VecDeque<u8>) {
+        let Some(&len) = self.key_lens.last() else {
+            return;
+        };
+        // The queue ends with the typed bytes that have not been read, after
+        // what remains of the input given up front
+        let unread = queue.len().min(self.echo.len());
+        if len > unread {
+            return;
+        }
+        queue.truncate(queue.len() - len);
+        self.echo.truncate(self.echo.len() - len);
+        self.key_lens.pop();
+    }
+}
+
+/// Read stdin on another thread, so that piped input reaches the script
+/// without blocking the UI. The channel closes at the end of stdin.
+fn spawn_stdin_reader() -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        let mut buf = [0; 4096];
+        while let Ok(len @ 1..) = stdin.read(&mut buf) {
+            if sender.send(buf[..len].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+enum Step {
+    Executed(char),
+    Done,
+    Error(Error),
+    Limit,
+}
+
 pub struct App {
     event_queue: EventQueue,
     show_tape: bool,
     ascii_values: bool,
+    width: Option<usize>,
     outfile: Option<PathBuf>,
+    max_steps: Option<u64>,
+    exit_code_from_cell: bool,
+    report: Option<String>,
+    report_file: Option<PathBuf>,
+    code: Vec<u8>,
+    initial_input: Vec<u8>,
+    schedule: Schedule,
     interpreter: Interpreter,
+    tracer: Option<Tracer>,
+    steps: u64,
+    paused: bool,
+    run_to_output: bool,
+    typed_input: TypedInput,
+    /// The input piped to stdin, read as it arrives, or `None` if the input
+    /// is typed.
+    stdin: Option<Receiver<Vec<u8>>>,
+    /// Everything read from `stdin` so far, to replay when stepping back.
+    piped_input: Vec<u8>,
+    /// Whether the input has ended, so that reads give 0.
+    input_ended: bool,
+    waiting_for_input: bool,
+    dirty: bool,
     render_state: AppWidgetState,
 }
 
@@ -51,92 +145,495 @@ impl App {
         set_panic_hook();
         enable_raw_mode()?;
 
-        let script_lines = read_script(cli.infile.as_ref())?;
-        let script = script_lines.iter().flat_map(String::as_bytes).copied();
+        // Keys are still read from the terminal when stdin is not one, but
+        // stdin is left alone when it holds the script
+        let script_on_stdin = cli
+            .infile
+            .as_ref()
+            .is_none_or(|path| path.as_os_str() == "-");
+        let stdin = (!script_on_stdin && !io::stdin().is_terminal())
+            .then(spawn_stdin_reader);
+        let event_queue = EventQueue::with_ticks(cli.delay);
+        Self::with_events(cli, event_queue, stdin)
+    }
 
-        let input = cli.input.into_bytes().into_iter().collect();
+    fn with_events(
+        cli: super::RunCli,
+        event_queue: EventQueue,
+        stdin: Option<Receiver<Vec<u8>>>,
+    ) -> Result<Self> {
+        let (code, options, source_map) = load_script(&cli)?;
+        let tracer = Tracer::new(&cli, source_map)?;
+        let initial_input = load_input(&cli)?;
+        let schedule = schedule(&cli);
+        let interpreter =
+            new_interpreter(&code, &initial_input, options, schedule);
 
         Ok(Self {
-            event_queue: EventQueue::with_ticks(cli.delay),
+            event_queue,
             show_tape: cli.show_tape,
             ascii_values: cli.ascii_values,
+            width: cli.width,
             outfile: cli.outfile,
-            interpreter: Interpreter::new(script, input, None),
+            max_steps: cli.max_steps,
+            exit_code_from_cell: cli.exit_code_from_cell,
+            report: cli.report,
+            report_file: cli.report_file,
+            code,
+            initial_input,
+            schedule,
+            interpreter,
+            tracer,
+            steps: 0,
+            paused: false,
+            run_to_output: false,
+            typed_input: TypedInput::default(),
+            stdin,
+            piped_input: Vec::new(),
+            input_ended: false,
+            waiting_for_input: false,
+            dirty: true,
             render_state: AppWidgetState::default(),
         })
     }
 
-    pub fn run(&mut self) -> Result<()> {
-        self.run_script()?;
+    /// Returns the exit code for the process.
+    pub fn run(&mut self) -> Result<u8> {
+        let (outcome, wall_time) = self.run_script()?;
+
+        if let Some(tracer) = self.tracer.take() {
+            tracer.finish()?;
+        }
 
         if let Some(path) = &self.outfile {
-            File::create(path)
-                .with_context(|| err_file_open!(path))?
-                .write_all(self.interpreter.output_bytes())
-                .with_context(|| err_file_write!(path))?;
+            write_outfile(path, self.interpreter.output_bytes())?;
         }
 
-        Ok(())
+        if let Some(format) = &self.report {
+            write_report(
+                format,
+                self.report_file.as_ref(),
+                &outcome,
+                &self.interpreter,
+                self.steps,
+                wall_time,
+            )?;
+        }
+
+        let cell = self
+            .exit_code_from_cell
+            .then(|| current_cell(&self.interpreter));
+        Ok(outcome.exit_code(cell))
     }
 
-    fn run_script(&mut self) -> Result<()> {
+    fn run_script(&mut self) -> Result<(Outcome, Duration)> {
         let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
         terminal.clear()?;
 
-        let script_err = self.run_script_loop(&mut terminal)?;
+        let start = Instant::now();
+        let outcome = self.run_script_loop(&mut terminal)?;
+        let wall_time = start.elapsed();
 
         reset_terminal();
         terminal
             .set_cursor_position((0, self.render_state.height))
             .ok();
 
-        if let Some(err) = script_err {
+        if let Outcome::Error(err) = &outcome {
             eprintln!();
             eprintln!("Error: {err}");
         }
 
-        Ok(())
+        Ok((outcome, wall_time))
     }
 
-    fn run_script_loop(
-        &mut self,
-        terminal: &mut Terminal,
-    ) -> Result<Option<Error>> {
-        self.draw_frame(terminal)?;
-
+    fn run_script_loop(&mut self, terminal: &mut Terminal) -> Result<Outcome> {
         'mainloop: loop {
+            if self.dirty {
+                self.draw_frame(terminal)?;
+                self.dirty = false;
+            }
+
             for event in self.event_queue.pop_all() {
-                match event {
-                    BfEvent::Tick => match self.interpreter.next() {
-                        Some(frame) => match frame {
-                            Ok(_) => self.draw_frame(terminal)?,
-                            Err(err) => break 'mainloop Ok(Some(err)),
-                        },
-                        None => break 'mainloop Ok(None),
-                    },
+                let step = match event {
+                    BfEvent::Tick => self.on_tick(),
                     BfEvent::Input(input_event) => match input_event {
                         Event::Key(e)
                             if e.is_ctrl() && e.code == KeyCode::Char('c') =>
                         {
-                            break 'mainloop Ok(None)
+                            break 'mainloop Ok(Outcome::Interrupted)
                         }
-                        _ => (),
+                        Event::Key(e) => self.on_key(e),
+                        _ => None,
                     },
                 };
+
+                match step {
+                    Some(Step::Done) => break 'mainloop Ok(Outcome::Done),
+                    Some(Step::Error(err)) => {
+                        break 'mainloop Ok(Outcome::Error(err))
+                    }
+                    Some(Step::Limit) => break 'mainloop Ok(Outcome::Limit),
+                    Some(Step::Executed(_)) | None => (),
+                }
+            }
+        }
+    }
+
+    fn is_waiting_for_input(&self) -> bool {
+        self.interpreter.needs_input()
+    }
+
+    fn step(&mut self) -> Step {
+        if self.max_steps == Some(self.steps) {
+            return Step::Limit;
+        }
+
+        let frame = match &mut self.tracer {
+            Some(tracer) => tracer.step(&mut self.interpreter, self.steps),
+            None => self.interpreter.next(),
+        };
+        match frame {
+            Some(Ok(ins)) => {
+                self.steps += 1;
+                self.dirty = true;
+                Step::Executed(ins)
             }
+            Some(Err(err)) => Step::Error(err),
+            None => Step::Done,
         }
     }
 
+    /// Go back one instruction by replaying the script from the beginning,
+    /// with all of the input given so far.
+    fn step_back(&mut self) {
+        let Some(target) = self.steps.checked_sub(1) else {
+            return;
+        };
+
+        let input = self
+            .initial_input
+            .iter()
+            .chain(&self.piped_input)
+            .chain(&self.typed_input.echo)
+            .copied()
+            .collect::<Vec<_>>();
+        self.interpreter = new_interpreter(
+            &self.code,
+            &input,
+            self.interpreter.options(),
+            self.schedule,
+        );
+        if self.input_ended {
+            self.interpreter.end_input();
+        }
+        for _ in 0..target {
+            self.interpreter.next();
+        }
+
+        self.steps = target;
+        self.dirty = true;
+    }
+
+    /// Run until the next output byte is printed, then pause.
+    fn continue_to_output(&mut self) -> Option<Step> {
+        for _ in 0..STEPS_PER_TICK {
+            if self.is_waiting_for_input() {
+                return None;
+            }
+            match self.step() {
+                Step::Executed('.') => {
+                    self.paused = true;
+                    self.run_to_output = false;
+                    return Some(Step::Executed('.'));
+                }
+                Step::Executed(_) => (),
+                step => return Some(step),
+            }
+        }
+        None
+    }
+
+    /// Queue the input that has arrived on stdin since the last tick.
+    fn read_stdin(&mut self) {
+        let Some(stdin) = &self.stdin else {
+            return;
+        };
+        loop {
+            match stdin.try_recv() {
+                Ok(bytes) => {
+                    self.interpreter.input.extend(&bytes);
+                    self.piped_input.extend(bytes);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.stdin = None;
+                    self.end_input();
+                    break;
+                }
+            }
+        }
+    }
+
+    const fn end_input(&mut self) {
+        self.input_ended = true;
+        self.interpreter.end_input();
+    }
+
+    fn on_tick(&mut self) -> Option<Step> {
+        self.read_stdin();
+
+        // Wait for keyboard input instead of letting the interpreter read
+        // from stdin
+        let waiting = self.is_waiting_for_input();
+        if waiting != self.waiting_for_input {
+            self.waiting_for_input = waiting;
+            self.dirty = true;
+        }
+
+        if waiting || self.paused {
+            None
+        } else if self.run_to_output {
+            self.continue_to_output()
+        } else {
+            Some(self.step())
+        }
+    }
+
+    fn on_key(&mut self, event: KeyEvent) -> Option<Step> {
+        self.dirty = true;
+
+        // Playback controls are only available in the visualizer, and only
+        // while no input is being typed
+        if !self.show_tape || self.is_waiting_for_input() {
+            self.on_input_key(event);
+            return None;
+        }
+
+        if event.is_ctrl() || event.is_alt() {
+            return None;
+        }
+
+        match event.code {
+            KeyCode::Char(' ') => {
+                self.paused ^= true;
+                self.run_to_output = false;
+            }
+            KeyCode::Char('n') => {
+                self.paused = true;
+                self.run_to_output = false;
+                return Some(self.step());
+            }
+            // The replay would record steps that are already in the trace
+            KeyCode::Char('b') if self.tracer.is_none() => {
+                self.paused = true;
+                self.run_to_output = false;
+                self.step_back();
+            }
+            KeyCode::Char('c') => {
+                self.paused = false;
+                self.run_to_output = true;
+            }
+            KeyCode::Char('+') => {
+                let delay = self.event_queue.tick_delay();
+                self.event_queue.set_tick_delay(delay / 2);
+            }
+            KeyCode::Char('-') => {
+                let delay = self.event_queue.tick_delay();
+                let delay = (delay * 2).clamp(1, MAX_DELAY);
+                self.event_queue.set_tick_delay(delay);
+            }
+            _ => (),
+        }
+
+        None
+    }
+
+    /// Queue the typed key as input for the script. Ctrl-D ends the input.
+    fn on_input_key(&mut self, event: KeyEvent) {
+        // The script reads piped input instead, and nothing after the end
+        if self.stdin.is_some() || self.input_ended {
+            return;
+        }
+
+        let mut buf = [0; 4];
+        let bytes: &[u8] = match event.code {
+            KeyCode::Char(c) if !event.is_ctrl() && !event.is_alt() => {
+                c.encode_utf8(&mut buf).as_bytes()
+            }
+            KeyCode::Enter => b"\n",
+            KeyCode::Tab => b"\t",
+            KeyCode::Char('d') if event.is_ctrl() => {
+                self.end_input();
+                return;
+            }
+            KeyCode::Backspace => {
+                self.typed_input.backspace(&mut self.interpreter.input);
+                return;
+            }
+            _ => return,
+        };
+
+        self.typed_input.push(&mut self.interpreter.input, bytes);
+    }
+
     fn draw_frame(&mut self, terminal: &mut Terminal) -> Result<()> {
+        let playback = self.show_tape.then(|| PlaybackStatus {
+            steps: self.steps,
+            paused: self.paused,
+            delay_ms: self.event_queue.tick_delay(),
+            can_step_back: self.tracer.is_none(),
+        });
         terminal.draw(|f| {
             let area = f.area();
             let widget = AppWidget {
                 show_tape: self.show_tape,
                 ascii_values: self.ascii_values,
+                width: self.width,
                 interpreter: &self.interpreter,
+                input_echo: &self.typed_input.echo,
+                waiting_for_input: self.waiting_for_input,
+                playback,
             };
             f.render_stateful_widget(widget, area, &mut self.render_state);
         })?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::VecDeque,
+        env, fs, process,
+        sync::mpsc::{self, Receiver},
+    };
+
+    use bf_tui::events::EventQueue;
+    use clap::Parser;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{App, Step, TypedInput};
+    use crate::commands::run::RunCli;
+
+    fn app(
+        name: &str,
+        script: &str,
+        args: &[&str],
+        stdin: Option<Receiver<Vec<u8>>>,
+    ) -> App {
+        let path = env::temp_dir()
+            .join(format!("bf-run-app-{}-{name}.b", process::id()));
+        fs::write(&path, script).unwrap();
+        let path = path.to_str().unwrap();
+        let cli = RunCli::try_parse_from(["run", path].iter().chain(args));
+        let app =
+            App::with_events(cli.unwrap(), EventQueue::detached(0), None);
+        fs::remove_file(path).unwrap();
+        let mut app = app.unwrap();
+        app.stdin = stdin;
+        app
+    }
+
+    fn key(app: &mut App, code: KeyCode) {
+        app.on_key(KeyEvent::from(code));
+    }
+
+    /// Tick until the script finishes, returning false if it waits for
+    /// input instead.
+    fn run(app: &mut App) -> bool {
+        for _ in 0..1000 {
+            match app.on_tick() {
+                Some(Step::Done) => return true,
+                Some(Step::Error(err)) => panic!("{err}"),
+                _ if app.waiting_for_input => return false,
+                _ => (),
+            }
+        }
+        panic!("the script did not stop");
+    }
+
+    #[test]
+    fn typed_input_ends_with_ctrl_d() {
+        let mut app = app("typed", ",[.,]", &[], None);
+        assert!(!run(&mut app));
+        key(&mut app, KeyCode::Char('h'));
+        key(&mut app, KeyCode::Char('i'));
+        key(&mut app, KeyCode::Enter);
+        assert!(!run(&mut app));
+        assert_eq!(app.interpreter.output, b"hi\n");
+
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        app.on_key(ctrl_d);
+        assert!(run(&mut app));
+        assert_eq!(app.interpreter.output, b"hi\n");
+        assert_eq!(app.typed_input.echo, b"hi\n");
+    }
+
+    #[test]
+    fn piped_input_is_read_as_it_arrives() {
+        let (sender, receiver) = mpsc::channel();
+        let mut app = app("piped", ",[.,]", &[], Some(receiver));
+        sender.send(b"hi".to_vec()).unwrap();
+        assert!(!run(&mut app));
+        assert_eq!(app.interpreter.output, b"hi");
+
+        // Keys are not input while stdin is piped
+        key(&mut app, KeyCode::Char('x'));
+        sender.send(b"!".to_vec()).unwrap();
+        drop(sender);
+        assert!(run(&mut app));
+        assert_eq!(app.interpreter.output, b"hi!");
+        assert!(app.typed_input.echo.is_empty());
+    }
+
+    #[test]
+    fn playback_keys_give_way_to_input() {
+        let mut app = app("playback", "++,.", &["-s"], None);
+        key(&mut app, KeyCode::Char(' '));
+        assert!(app.paused);
+        key(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.steps, 1);
+        key(&mut app, KeyCode::Char('b'));
+        assert_eq!(app.steps, 0);
+
+        key(&mut app, KeyCode::Char('n'));
+        key(&mut app, KeyCode::Char('n'));
+        assert!(app.is_waiting_for_input());
+        // While waiting, `n` is typed instead of stepping
+        key(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.steps, 2);
+        key(&mut app, KeyCode::Char('n'));
+        key(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.interpreter.output, b"n");
+        assert!(matches!(app.step(), Step::Done));
+    }
+
+    #[test]
+    fn backspace_removes_whole_keys() {
+        let mut typed = TypedInput::default();
+        let mut queue = VecDeque::new();
+        typed.push(&mut queue, b"a");
+        typed.push(&mut queue, "é".as_bytes());
+        typed.backspace(&mut queue);
+        assert_eq!(queue, b"a");
+        assert_eq!(typed.echo, b"a");
+    }
+
+    #[test]
+    fn backspace_keeps_read_and_preloaded_input() {
+        let mut typed = TypedInput::default();
+        let mut queue = VecDeque::from(b"xy".to_vec());
+        typed.push(&mut queue, b"a");
+        typed.backspace(&mut queue);
+        typed.backspace(&mut queue);
+        assert_eq!(queue, b"xy");
+
+        // The script has read the preloaded input and the first byte of é
+        typed.push(&mut queue, "é".as_bytes());
+        queue.drain(..3);
+        typed.backspace(&mut queue);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(typed.echo, "é".as_bytes());
+    }
+}
@@ -1,29 +1,102 @@
-use std::path::PathBuf;
+use std::{
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    process,
+};
 
 use anyhow::Result;
 use clap::Parser;
 
-use crate::cli::{parse_infile, parse_width, ClapError, SubCmd};
+use crate::{
+    cli::{parse_infile, parse_width, ClapError, SubCmd},
+    utils::{
+        dialect::{parse_run_dialect, COMPAT_HELP, RUN_DIALECT_HELP},
+        input::INPUT_FORMATS,
+        trace::TRACE_FORMATS,
+    },
+};
 
-use super::app::App;
+use super::{app::App, plain, report::REPORT_FORMATS, trace::TRACE_FILTERS};
 
 const ABOUT: &str = "Execute a Brainfuck script (aliases: r)";
 const DELAY_HELP: &str = "The delay, in milliseconds, between the evaluation \
                           of each Brainfuck instruction. Does nothing if \
                           -s/--show-tape is not given.";
 const INPUT_HELP: &str = "The input to provide the Brainfuck program for the \
-                          read (,) instruction.";
+                          read (,) instruction. Once it runs out, the \
+                          program reads stdin, or the keys typed in the \
+                          visualizer if stdin is a terminal. In the \
+                          visualizer, Ctrl-D ends the typed input and \
+                          every read after the end of the input gives 0.";
+const INPUT_FORMAT_HELP: &str = "How to interpret -i/--input: as plain text, \
+                                 as text with the escape sequences \\n, \\r, \
+                                 \\t, \\0, \\\\, and \\xHH (escaped), or as \
+                                 pairs of hex digits (hex).";
+const INPUT_FILE_HELP: &str = "A file whose exact bytes are provided to the \
+                               Brainfuck program as input.";
+const FORK_SEED_HELP: &str = "Give each thread of a brainfork script turns \
+                              of 1 to 16 instructions, picked at random \
+                              from this seed, instead of one instruction \
+                              each.";
 const SHOW_HELP: &str = "Show the tape during execution. Use -d/--delay to \
-                         slow down execution.";
+                         slow down execution. Requires stdout to be a \
+                         terminal.";
+const RAW_HELP: &str = "Stream the output to stdout as it is produced and \
+                        read input from stdin without drawing to or \
+                        configuring the terminal. This is the default when \
+                        stdout is not a terminal.";
 const WIDTH_HELP: &str = "The maximum width of the terminal for formatting \
                           the tape output.";
 const ASCII_HELP: &str = "Show the ASCII characters in the tape output \
                           instead of the decimal values.";
+const TRACE_HELP: &str = "Record each executed instruction to this file. \
+                          Stepping back in the visualizer is disabled \
+                          while tracing.";
+const TRACE_FORMAT_HELP: &str = "The format of the trace file. Defaults to \
+                                 csv if the trace file ends in .csv, \
+                                 otherwise jsonl.";
+const TRACE_FILTER_HELP: &str = "Which instructions to record: all of them, \
+                                 only reads and writes (io), or only loop \
+                                 entries (loops).";
+const TRACE_EVERY_HELP: &str = "Only record every Nth instruction that \
+                                passes the filter.";
+const MAX_STEPS_HELP: &str = "Stop the script after executing this many \
+                              instructions.";
+const REPORT_HELP: &str = "Print a summary of the run to stderr in this \
+                           format once the script stops: the exit reason, \
+                           steps executed, wall time, final tape and \
+                           cursor, highest cell touched, bytes read and \
+                           written, and the output encoded as base64.";
+const REPORT_FILE_HELP: &str = "Write the --report summary to this file \
+                                instead of stderr.";
+const EXIT_CODE_FROM_CELL_HELP: &str = "Exit with the value of the current \
+                                       cell when the script finishes. The \
+                                       value may be the same as one of the \
+                                       exit codes for errors, which \
+                                       --report tells apart.";
+pub const EXIT_CODES_HELP: &str = "Exit codes:
+  0    The script finished
+  1    The script could not be loaded or another error occurred
+  2    The command line arguments are invalid
+  3    The script has mismatched brackets
+  4    The script failed to read input from stdin
+  5    The script was stopped by -m/--max-steps
+  6    The script called a pbrain procedure that is not defined
+  7    The script nested pbrain procedure calls too deeply
+  130  The visualizer was quit with Ctrl-C";
 const INFILE_HELP: &str = "The path to the Brainfuck script to execute. Read \
                            from stdin if infile is a dash (-) or is omitted.";
 const OUTFILE_HELP: &str = "The name of the file to which the final output \
                             of the Brainfuck script will be printed.";
 
+fn parse_every(value: &str) -> Result<u64, ClapError> {
+    match value.parse::<u64>() {
+        Ok(0) => Err("value must be an integer > 0".into()),
+        Ok(n) => Ok(n),
+        Err(err) => Err(err.into()),
+    }
+}
+
 fn parse_delay(value: &str) -> Result<u64, ClapError> {
     match value.parse::<i64>() {
         Ok(n) => {
@@ -38,7 +111,7 @@ fn parse_delay(value: &str) -> Result<u64, ClapError> {
 }
 
 #[derive(Debug, Parser)]
-#[command(about=ABOUT)]
+#[command(about=ABOUT, after_help=EXIT_CODES_HELP)]
 pub struct RunCli {
     #[arg(
         short,
@@ -59,9 +132,37 @@ pub struct RunCli {
     )]
     pub input: String,
 
-    #[arg(short, long, help=SHOW_HELP)]
+    #[arg(
+        long,
+        default_value="text",
+        value_parser=INPUT_FORMATS,
+        help=INPUT_FORMAT_HELP
+    )]
+    pub input_format: String,
+
+    #[arg(short='I', long, conflicts_with="input", help=INPUT_FILE_HELP)]
+    pub input_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value="brainfuck",
+        value_parser=parse_run_dialect,
+        help=RUN_DIALECT_HELP
+    )]
+    pub dialect: String,
+
+    #[arg(long, help=COMPAT_HELP)]
+    pub compat: bool,
+
+    #[arg(long, help=FORK_SEED_HELP)]
+    pub fork_seed: Option<u64>,
+
+    #[arg(short, long, conflicts_with="raw", help=SHOW_HELP)]
     pub show_tape: bool,
 
+    #[arg(short, long, help=RAW_HELP)]
+    pub raw: bool,
+
     #[arg(short, long, value_parser=parse_width, help=WIDTH_HELP)]
     pub width: Option<usize>,
 
@@ -71,12 +172,64 @@ pub struct RunCli {
     #[arg(short, long, help=OUTFILE_HELP)]
     pub outfile: Option<PathBuf>,
 
+    #[arg(short, long, help=MAX_STEPS_HELP)]
+    pub max_steps: Option<u64>,
+
+    #[arg(long, help=EXIT_CODE_FROM_CELL_HELP)]
+    pub exit_code_from_cell: bool,
+
+    #[arg(long, value_parser=REPORT_FORMATS, help=REPORT_HELP)]
+    pub report: Option<String>,
+
+    #[arg(long, requires="report", help=REPORT_FILE_HELP)]
+    pub report_file: Option<PathBuf>,
+
+    #[arg(long, help=TRACE_HELP)]
+    pub trace: Option<PathBuf>,
+
+    #[arg(long, value_parser=TRACE_FORMATS, help=TRACE_FORMAT_HELP)]
+    pub trace_format: Option<String>,
+
+    #[arg(
+        long,
+        default_value="all",
+        value_parser=TRACE_FILTERS,
+        help=TRACE_FILTER_HELP
+    )]
+    pub trace_filter: String,
+
+    #[arg(
+        long,
+        default_value="1",
+        value_parser=parse_every,
+        help=TRACE_EVERY_HELP
+    )]
+    pub trace_every: u64,
+
     #[arg(value_parser=parse_infile, help=INFILE_HELP)]
     pub infile: Option<PathBuf>,
 }
 
 impl SubCmd for RunCli {
     fn run(self) -> Result<()> {
-        App::new(self)?.run()
+        let is_terminal = io::stdout().is_terminal();
+        if self.show_tape && !is_terminal {
+            eprintln!(
+                "Warning: ignoring -s/--show-tape because stdout is not a \
+                 terminal"
+            );
+        }
+
+        let code = if self.raw || !is_terminal {
+            plain::run(self)?
+        } else {
+            App::new(self)?.run()?
+        };
+
+        if code != 0 {
+            io::stdout().flush().ok();
+            process::exit(code.into());
+        }
+        Ok(())
     }
 }
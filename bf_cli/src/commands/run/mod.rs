@@ -1,4 +1,137 @@
+use std::{fs, fs::File, io::Write, path::PathBuf};
+
+use anyhow::{Context, Error, Result};
+use bf::interpreter::{ErrorKind, Interpreter, Options, Schedule};
+
+use crate::{
+    err_file_open, err_file_read, err_file_write,
+    utils::{
+        dialect::RunDialect, input::parse_input, read::read_script,
+        source_map::SourceMap,
+    },
+};
+
 mod app;
 
 mod cli;
 pub use cli::RunCli;
+
+mod plain;
+
+mod report;
+
+mod trace;
+
+/// How a run of a script ended.
+pub enum Outcome {
+    Done,
+    Error(Error),
+    /// The step limit given with `--max-steps` was reached.
+    Limit,
+    /// The user quit the visualizer before the script finished.
+    Interrupted,
+}
+
+impl Outcome {
+    /// The exit code of the process. A script that finishes exits with
+    /// `cell`, the value of the current cell with `--exit-code-from-cell`,
+    /// or 0. See [`cli::EXIT_CODES_HELP`].
+    fn exit_code(&self, cell: Option<u8>) -> u8 {
+        match self {
+            Self::Done => cell.unwrap_or(0),
+            Self::Error(err) => match err.downcast_ref::<ErrorKind>() {
+                Some(ErrorKind::MismatchedBrackets) => 3,
+                Some(ErrorKind::ReadInput) => 4,
+                Some(ErrorKind::UndefinedProcedure(_)) => 6,
+                Some(ErrorKind::CallStackOverflow) => 7,
+                None => 1,
+            },
+            Self::Limit => 5,
+            Self::Interrupted => 130,
+        }
+    }
+}
+
+fn current_cell(interpreter: &Interpreter) -> u8 {
+    let tape = &interpreter.tape;
+    tape.value(tape.cursor())
+}
+
+/// Read the script, translated to Brainfuck, and the options to run it with.
+fn load_script(cli: &RunCli) -> Result<(Vec<u8>, Options, SourceMap)> {
+    let dialect = RunDialect::load(&cli.dialect)?;
+    let script = read_script(cli.infile.as_ref())?;
+    let options = dialect.options(cli.compat);
+    let source_map = dialect.source_map(&script);
+    Ok((dialect.decode(script), options, source_map))
+}
+
+/// How the threads of a brainfork script take turns.
+fn schedule(cli: &RunCli) -> Schedule {
+    cli.fork_seed.map_or(Schedule::RoundRobin, Schedule::Random)
+}
+
+fn load_input(cli: &RunCli) -> Result<Vec<u8>> {
+    match &cli.input_file {
+        Some(path) => fs::read(path).with_context(|| err_file_read!(path)),
+        None => parse_input(&cli.input, &cli.input_format)
+            .context("failed to parse the input"),
+    }
+}
+
+fn new_interpreter(
+    script: &[u8],
+    input: &[u8],
+    options: Options,
+    schedule: Schedule,
+) -> Interpreter {
+    let mut interpreter = Interpreter::with_options(
+        script.iter().copied(),
+        input.iter().copied().collect(),
+        None,
+        options,
+    );
+    interpreter.set_schedule(schedule);
+    interpreter
+}
+
+fn write_outfile(path: &PathBuf, output: &[u8]) -> Result<()> {
+    File::create(path)
+        .with_context(|| err_file_open!(path))?
+        .write_all(output)
+        .with_context(|| err_file_write!(path))
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::{anyhow, Error};
+    use bf::interpreter::ErrorKind;
+
+    use super::Outcome;
+
+    #[test]
+    fn exit_codes() {
+        let error = |kind: ErrorKind| Outcome::Error(Error::new(kind));
+        assert_eq!(Outcome::Done.exit_code(None), 0);
+        assert_eq!(Outcome::Error(anyhow!("other")).exit_code(None), 1);
+        assert_eq!(error(ErrorKind::MismatchedBrackets).exit_code(None), 3);
+        assert_eq!(error(ErrorKind::ReadInput).exit_code(None), 4);
+        assert_eq!(Outcome::Limit.exit_code(None), 5);
+        assert_eq!(error(ErrorKind::UndefinedProcedure(1)).exit_code(None), 6);
+        assert_eq!(error(ErrorKind::CallStackOverflow).exit_code(None), 7);
+        assert_eq!(Outcome::Interrupted.exit_code(None), 130);
+    }
+
+    #[test]
+    fn exit_code_from_cell() {
+        assert_eq!(Outcome::Done.exit_code(Some(42)), 42);
+        // Only a script that finishes exits with the cell
+        assert_eq!(Outcome::Limit.exit_code(Some(42)), 5);
+    }
+
+    #[test]
+    fn context_keeps_the_error_kind() {
+        let err = Error::new(ErrorKind::ReadInput).context("while running");
+        assert_eq!(Outcome::Error(err).exit_code(None), 4);
+    }
+}
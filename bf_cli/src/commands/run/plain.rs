@@ -0,0 +1,93 @@
+use std::{
+    io::{self, Write},
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+
+use crate::err_print;
+
+use super::{
+    current_cell, load_input, load_script, new_interpreter,
+    report::write_report, schedule, trace::Tracer, write_outfile, Outcome,
+    RunCli,
+};
+
+/// Run the script without touching the terminal, streaming its output to
+/// stdout as it is produced. Used when stdout is not a TTY or with `--raw`.
+///
+/// Returns the exit code for the process.
+pub fn run(cli: RunCli) -> Result<u8> {
+    let (script, options, source_map) = load_script(&cli)?;
+    let input = load_input(&cli)?;
+    let mut interpreter =
+        new_interpreter(&script, &input, options, schedule(&cli));
+    let mut tracer = Tracer::new(&cli, source_map)?;
+    let mut stdout = io::stdout().lock();
+
+    let start = Instant::now();
+    let mut steps = 0;
+    let mut written = 0;
+    let outcome = loop {
+        if cli.max_steps == Some(steps) {
+            break Outcome::Limit;
+        }
+
+        let frame = match &mut tracer {
+            Some(tracer) => tracer.step(&mut interpreter, steps),
+            None => interpreter.next(),
+        };
+        // Boolfuck writes a byte every eight bits and flushes the last one
+        // when the script finishes, so check for new output after every step
+        written += stream(&mut stdout, &interpreter.output[written..])?;
+        let Some(frame) = frame else {
+            break Outcome::Done;
+        };
+        steps += 1;
+
+        if let Err(err) = frame {
+            break Outcome::Error(err);
+        }
+    };
+    let wall_time = start.elapsed();
+
+    if let Some(tracer) = tracer {
+        tracer.finish()?;
+    }
+
+    if let Some(path) = &cli.outfile {
+        write_outfile(path, interpreter.output_bytes())?;
+    }
+
+    // A report on stderr includes the error
+    let report_on_stderr = cli.report.is_some() && cli.report_file.is_none();
+    if let (Outcome::Error(err), false) = (&outcome, report_on_stderr) {
+        eprintln!();
+        eprintln!("Error: {err}");
+    }
+
+    if let Some(format) = &cli.report {
+        write_report(
+            format,
+            cli.report_file.as_ref(),
+            &outcome,
+            &interpreter,
+            steps,
+            wall_time,
+        )?;
+    }
+
+    let cell = cli.exit_code_from_cell.then(|| current_cell(&interpreter));
+    Ok(outcome.exit_code(cell))
+}
+
+/// Write the new output of the script to stdout, returning its length.
+fn stream(stdout: &mut impl Write, output: &[u8]) -> Result<usize> {
+    if !output.is_empty() {
+        stdout
+            .write_all(output)
+            .and_then(|()| stdout.flush())
+            .with_context(|| err_print!())?;
+    }
+    Ok(output.len())
+}
@@ -0,0 +1,101 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bf::interpreter::Interpreter;
+use serde::Serialize;
+
+use crate::err_print;
+
+use super::{write_outfile, Outcome};
+
+pub const REPORT_FORMATS: [&str; 2] = ["json", "toml"];
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ExitReason {
+    Done,
+    Error,
+    Limit,
+    Interrupted,
+}
+
+/// A summary of a run of a script, for `--report`.
+#[derive(Serialize)]
+struct Report {
+    exit_reason: ExitReason,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    steps: u64,
+    wall_time_ms: f64,
+    tape: Vec<u8>,
+    cursor: usize,
+    highest_cell: usize,
+    bytes_read: usize,
+    bytes_written: usize,
+    output_base64: String,
+}
+
+impl Report {
+    fn new(
+        outcome: &Outcome,
+        interpreter: &Interpreter,
+        steps: u64,
+        wall_time: Duration,
+    ) -> Self {
+        let (exit_reason, error) = match outcome {
+            Outcome::Done => (ExitReason::Done, None),
+            Outcome::Error(err) => (ExitReason::Error, Some(err.to_string())),
+            Outcome::Limit => (ExitReason::Limit, None),
+            Outcome::Interrupted => (ExitReason::Interrupted, None),
+        };
+        let tape = &interpreter.tape;
+        Self {
+            exit_reason,
+            error,
+            steps,
+            wall_time_ms: wall_time.as_secs_f64() * 1000.0,
+            tape: tape.values().collect(),
+            cursor: tape.cursor(),
+            // The tape only grows to the right when the cursor moves there
+            highest_cell: tape.len() - 1,
+            bytes_read: interpreter.bytes_read(),
+            bytes_written: interpreter.output_bytes().len(),
+            output_base64: BASE64.encode(interpreter.output_bytes()),
+        }
+    }
+
+    fn serialize(&self, format: &str) -> Result<String> {
+        Ok(match format {
+            "json" => serde_json::to_string_pretty(self)? + "\n",
+            "toml" => toml::to_string(self)?,
+            _ => bail!("invalid report format (impossible): {format}"),
+        })
+    }
+}
+
+/// Write the summary of the run in `format` to `path`, or to stderr so that
+/// it is kept apart from the output of the script.
+pub fn write_report(
+    format: &str,
+    path: Option<&PathBuf>,
+    outcome: &Outcome,
+    interpreter: &Interpreter,
+    steps: u64,
+    wall_time: Duration,
+) -> Result<()> {
+    let report = Report::new(outcome, interpreter, steps, wall_time)
+        .serialize(format)
+        .context("failed to serialize the run report")?;
+
+    match path {
+        Some(path) => write_outfile(path, report.as_bytes()),
+        None => io::stderr()
+            .write_all(report.as_bytes())
+            .with_context(|| err_print!()),
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{bail, Error, Result};
+use bf::interpreter::Interpreter;
+
+use crate::utils::{
+    source_map::SourceMap,
+    trace::{TraceEvent, TraceFormat, TraceWriter},
+};
+
+use super::RunCli;
+
+pub const TRACE_FILTERS: [&str; 3] = ["all", "io", "loops"];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TraceFilter {
+    All,
+    /// Only the read (,) and write (.) instructions.
+    Io,
+    /// Only opening brackets that enter their loop.
+    Loops,
+}
+
+/// Records the instructions executed by an interpreter to a trace file.
+pub struct Tracer {
+    writer: TraceWriter,
+    source_map: SourceMap,
+    filter: TraceFilter,
+    every: u64,
+    matched: u64,
+    error: Option<Error>,
+}
+
+impl Tracer {
+    /// Record to the trace file given to `cli`, if any. `source_map` gives
+    /// the position of each instruction in the script as it is written.
+    pub fn new(cli: &RunCli, source_map: SourceMap) -> Result<Option<Self>> {
+        let Some(path) = &cli.trace else {
+            return Ok(None);
+        };
+
+        let format = TraceFormat::new(cli.trace_format.as_deref(), path)?;
+        let filter = match &*cli.trace_filter {
+            "all" => TraceFilter::All,
+            "io" => TraceFilter::Io,
+            "loops" => TraceFilter::Loops,
+            filter => bail!("invalid trace filter (impossible): {filter}"),
+        };
+
+        Ok(Some(Self {
+            writer: TraceWriter::create(path, format)?,
+            source_map,
+            filter,
+            every: cli.trace_every,
+            matched: 0,
+            error: None,
+        }))
+    }
+
+    /// Execute the next instruction, recording it if it passes the filter
+    /// and sampling. `step` is the number of instructions executed so far.
+    pub fn step(
+        &mut self,
+        int: &mut Interpreter,
+        step: u64,
+    ) -> Option<Result<char>> {
+        let ip = int.ip();
+        let pointer = int.tape.cursor();
+        let before = int.tape.current().value();
+
+        let frame = int.next();
+
+        if let Some(Ok(instruction)) = frame {
+            let is_match = match self.filter {
+                TraceFilter::All => true,
+                TraceFilter::Io => matches!(instruction, '.' | ','),
+                TraceFilter::Loops => instruction == '[' && int.ip() == ip + 1,
+            };
+            if is_match {
+                self.matched += 1;
+                if (self.matched - 1).is_multiple_of(self.every) {
+                    let pos = self.source_map.get(ip).unwrap_or_else(|| {
+                        unreachable!("no source position for ip {ip}")
+                    });
+                    let event = TraceEvent {
+                        step: step + 1,
+                        ip,
+                        line: pos.line + 1,
+                        column: pos.column + 1,
+                        instruction,
+                        pointer,
+                        before,
+                        after: int.tape.value(pointer),
+                    };
+                    self.write(&event);
+                }
+            }
+        }
+
+        frame
+    }
+
+    fn write(&mut self, event: &TraceEvent) {
+        // Keep running the script, but stop tracing, after the first error
+        if self.error.is_none() {
+            if let Err(err) = self.writer.write(event) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<()> {
+        match self.error {
+            Some(err) => Err(err),
+            None => self.writer.finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use bf::interpreter::Interpreter;
+    use clap::Parser;
+
+    use crate::utils::{
+        dialect::RunDialect,
+        trace::{read_trace, TraceEvent, TraceFormat},
+    };
+
+    use super::{RunCli, Tracer};
+
+    const SCRIPT: &[u8] = b",.\n+[-]";
+
+    /// Trace `SCRIPT` with the extra `args` of `bf run`, then read the trace
+    /// back.
+    fn round_trip(name: &str, args: &[&str]) -> Vec<TraceEvent> {
+        let path =
+            env::temp_dir().join(format!("bf-trace-{}-{name}", process::id()));
+        let path_arg = path.to_string_lossy();
+        let cli = RunCli::parse_from(
+            ["run", "--trace", &path_arg]
+                .iter()
+                .chain(args)
+                .chain(&["-"]),
+        );
+
+        let source_map =
+            RunDialect::load("brainfuck").unwrap().source_map(SCRIPT);
+        let mut tracer = Tracer::new(&cli, source_map).unwrap().unwrap();
+        let mut int =
+            Interpreter::new(SCRIPT.iter().copied(), [1].into(), None);
+        let mut steps = 0;
+        while let Some(frame) = tracer.step(&mut int, steps) {
+            frame.unwrap();
+            steps += 1;
+        }
+        tracer.finish().unwrap();
+
+        let format = TraceFormat::new(cli.trace_format.as_deref(), &path);
+        let events = read_trace(&path, format.unwrap()).unwrap();
+        fs::remove_file(&path).ok();
+        events
+    }
+
+    const fn event(
+        step: u64,
+        ip: usize,
+        (line, column): (usize, usize),
+        instruction: char,
+        (before, after): (u8, u8),
+    ) -> TraceEvent {
+        TraceEvent {
+            step,
+            ip,
+            line,
+            column,
+            instruction,
+            pointer: 0,
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn all_instructions() {
+        for name in ["all.jsonl", "all.csv"] {
+            let events = round_trip(name, &[]);
+            let steps = events.iter().map(|e| e.step).collect::<Vec<_>>();
+            assert_eq!(steps, [1, 2, 3, 4, 5, 6, 7, 8], "{name}");
+            assert_eq!(events[2], event(3, 2, (2, 1), '+', (1, 2)), "{name}");
+            assert_eq!(events[7], event(8, 5, (2, 4), ']', (0, 0)), "{name}");
+        }
+    }
+
+    #[test]
+    fn io_filter() {
+        for format in ["jsonl", "csv"] {
+            let args = ["--trace-format", format, "--trace-filter", "io"];
+            let events = round_trip(&format!("io-{format}"), &args);
+            assert_eq!(
+                events,
+                [
+                    event(1, 0, (1, 1), ',', (0, 1)),
+                    event(2, 1, (1, 2), '.', (1, 1)),
+                ],
+                "{format}"
+            );
+        }
+    }
+
+    #[test]
+    fn loops_filter() {
+        for name in ["loops.jsonl", "loops.csv"] {
+            let events = round_trip(name, &["--trace-filter", "loops"]);
+            assert_eq!(events, [event(4, 3, (2, 2), '[', (2, 2))], "{name}");
+        }
+    }
+
+    #[test]
+    fn sampling() {
+        for name in ["every.jsonl", "every.csv"] {
+            let events = round_trip(name, &["--trace-every", "3"]);
+            let steps = events.iter().map(|e| e.step).collect::<Vec<_>>();
+            assert_eq!(steps, [1, 4, 7], "{name}");
+        }
+    }
+}
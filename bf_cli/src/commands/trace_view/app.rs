@@ -0,0 +1,331 @@
+use std::{io::stdout, thread, time::Duration};
+
+use anyhow::{bail, Result};
+use bf::interpreter::Tape;
+use bf_tui::{
+    events::{BfEvent, EventQueue},
+    sublayouts,
+    widgets::{ChunkedTapeState, ChunkedTapeWidget},
+    Terminal,
+};
+use crossterm::{
+    event::{Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    widgets::{Block, Paragraph, Row, Table},
+    Frame,
+};
+
+use crate::utils::trace::TraceEvent;
+
+/// The number of events skipped by Page Up and Page Down.
+const PAGE_SIZE: usize = 20;
+
+const KEYS: &str = "←/→ step  PgUp/PgDn page  Home/End jump  q quit";
+
+/// The current event of a trace, and the tape as it is after it.
+struct Replay {
+    events: Vec<TraceEvent>,
+    /// The index of the current event.
+    current: usize,
+    /// The tape as it is after the current event. Only the cells touched by
+    /// recorded events are known, every other cell is shown as 0.
+    tape: Tape,
+    /// The number of events replayed onto `tape`.
+    applied: usize,
+}
+
+impl Replay {
+    fn new(events: Vec<TraceEvent>) -> Result<Self> {
+        if events.is_empty() {
+            bail!("the trace is empty");
+        }
+
+        let mut replay = Self {
+            events,
+            current: 0,
+            tape: Tape::default(),
+            applied: 0,
+        };
+        replay.seek(0);
+        Ok(replay)
+    }
+
+    /// Move to another event. Returns false if the viewer should quit.
+    fn on_key(&mut self, code: KeyCode) -> bool {
+        let last = self.events.len() - 1;
+        let target = match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Right | KeyCode::Char('n') => self.current + 1,
+            KeyCode::Left | KeyCode::Char('p') => {
+                self.current.saturating_sub(1)
+            }
+            KeyCode::PageDown => self.current + PAGE_SIZE,
+            KeyCode::PageUp => self.current.saturating_sub(PAGE_SIZE),
+            KeyCode::Home => 0,
+            KeyCode::End => last,
+            _ => return true,
+        };
+        self.seek(target.min(last));
+        true
+    }
+
+    /// Make `index` the current event, replaying the recorded cell values
+    /// from the start of the trace when moving backwards.
+    fn seek(&mut self, index: usize) {
+        if index + 1 < self.applied {
+            self.tape = Tape::default();
+            self.applied = 0;
+        }
+        for event in &self.events[self.applied..=index] {
+            self.tape.seek(event.pointer);
+            self.tape.current().set(event.after);
+        }
+        self.applied = index + 1;
+        self.current = index;
+    }
+}
+
+pub struct App {
+    replay: Replay,
+    tape_state: ChunkedTapeState,
+}
+
+impl Drop for App {
+    fn drop(&mut self) {
+        execute!(stdout(), LeaveAlternateScreen).ok();
+        disable_raw_mode().ok();
+    }
+}
+
+impl App {
+    pub fn new(events: Vec<TraceEvent>) -> Result<Self> {
+        let replay = Replay::new(events)?;
+
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen)?;
+
+        Ok(Self {
+            replay,
+            tape_state: ChunkedTapeState::default(),
+        })
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+        let event_queue = EventQueue::with_ticks(100);
+        let delay = Duration::from_millis(5);
+
+        terminal.draw(|f| self.draw(f))?;
+        'main: loop {
+            let mut dirty = false;
+            for bf_event in event_queue.pop_all() {
+                match bf_event {
+                    BfEvent::Input(Event::Key(event))
+                        if event.kind != KeyEventKind::Release =>
+                    {
+                        if !self.replay.on_key(event.code) {
+                            break 'main;
+                        }
+                        dirty = true;
+                    }
+                    BfEvent::Input(Event::Resize(..)) => dirty = true,
+                    _ => {}
+                }
+            }
+
+            if dirty {
+                terminal.draw(|f| self.draw(f))?;
+            }
+            thread::sleep(delay);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let width = i32::from(frame.area().width);
+        let tape = ChunkedTapeWidget::new(&self.replay.tape, width, false);
+
+        let layout = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Max(tape.height()),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .split(frame.area());
+        sublayouts!(
+            [header_area, tape_area, details_area, events_area] = layout
+        );
+
+        let title = format!(
+            "Trace Viewer ({}/{})  {KEYS}",
+            self.replay.current + 1,
+            self.replay.events.len()
+        );
+        frame.render_widget(Paragraph::new(title), header_area);
+        frame.render_stateful_widget(tape, tape_area, &mut self.tape_state);
+        self.draw_details(frame, details_area);
+        self.draw_events(frame, events_area);
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let event = &self.replay.events[self.replay.current];
+        let details = format!(
+            "Step {}: '{}' at {}:{} (ip {}), cell {}: {} -> {}",
+            event.step,
+            event.instruction,
+            event.line,
+            event.column,
+            event.ip,
+            event.pointer,
+            event.before,
+            event.after,
+        );
+        let paragraph = Paragraph::new(details).block(Block::bordered());
+        frame.render_widget(paragraph, area);
+    }
+
+    fn draw_events(&self, frame: &mut Frame, area: Rect) {
+        // Keep the current event in the middle of the table where possible
+        let rows = area.height.saturating_sub(3) as usize;
+        let start = self
+            .replay
+            .current
+            .saturating_sub(rows / 2)
+            .min(self.replay.events.len().saturating_sub(rows));
+
+        let header = Row::new(vec![
+            "Step", "Pos", "Ip", "Instr", "Ptr", "Before", "After",
+        ])
+        .bold();
+        let items = self
+            .replay
+            .events
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(rows)
+            .map(|(i, event)| {
+                let row = Row::new(vec![
+                    event.step.to_string(),
+                    format!("{}:{}", event.line, event.column),
+                    event.ip.to_string(),
+                    event.instruction.to_string(),
+                    event.pointer.to_string(),
+                    event.before.to_string(),
+                    event.after.to_string(),
+                ]);
+                if i == self.replay.current {
+                    row.reversed()
+                } else {
+                    row
+                }
+            });
+        let table = Table::new(
+            items,
+            [
+                Constraint::Length(10),
+                Constraint::Length(9),
+                Constraint::Length(8),
+                Constraint::Length(5),
+                Constraint::Length(8),
+                Constraint::Length(6),
+                Constraint::Length(5),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered())
+        .column_spacing(2);
+        frame.render_widget(table, area);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crossterm::event::KeyCode;
+
+    use super::{Replay, PAGE_SIZE};
+    use crate::utils::trace::TraceEvent;
+
+    /// Each event adds 1 to the cell under the pointer, which moves right
+    /// every 10 steps.
+    fn events(len: usize) -> Vec<TraceEvent> {
+        (0..len)
+            .map(|i| TraceEvent {
+                step: i as u64 + 1,
+                ip: 0,
+                line: 1,
+                column: 1,
+                instruction: '+',
+                pointer: i / 10,
+                before: (i % 10) as u8,
+                after: (i % 10) as u8 + 1,
+            })
+            .collect()
+    }
+
+    fn cells(replay: &Replay) -> Vec<u8> {
+        replay.tape.values().collect()
+    }
+
+    #[test]
+    fn empty_trace() {
+        assert!(Replay::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn steps_stay_in_the_trace() {
+        let mut replay = Replay::new(events(3)).unwrap();
+        assert_eq!(replay.current, 0);
+        assert!(replay.on_key(KeyCode::Left));
+        assert_eq!(replay.current, 0);
+        replay.on_key(KeyCode::Right);
+        replay.on_key(KeyCode::Char('n'));
+        replay.on_key(KeyCode::Right);
+        assert_eq!(replay.current, 2);
+        replay.on_key(KeyCode::Char('p'));
+        assert_eq!(replay.current, 1);
+        assert!(replay.on_key(KeyCode::Char('x')));
+        assert_eq!(replay.current, 1);
+        assert!(!replay.on_key(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn pages_and_jumps() {
+        let mut replay = Replay::new(events(45)).unwrap();
+        replay.on_key(KeyCode::PageDown);
+        assert_eq!(replay.current, PAGE_SIZE);
+        replay.on_key(KeyCode::End);
+        assert_eq!(replay.current, 44);
+        replay.on_key(KeyCode::PageDown);
+        assert_eq!(replay.current, 44);
+        replay.on_key(KeyCode::PageUp);
+        assert_eq!(replay.current, 44 - PAGE_SIZE);
+        replay.on_key(KeyCode::Home);
+        assert_eq!(replay.current, 0);
+    }
+
+    #[test]
+    fn seek_replays_the_tape() {
+        let mut replay = Replay::new(events(25)).unwrap();
+        assert_eq!(cells(&replay), [1]);
+        replay.seek(24);
+        assert_eq!(cells(&replay), [10, 10, 5]);
+        // Going back starts over, so later values are not kept
+        replay.seek(12);
+        assert_eq!(cells(&replay), [10, 3]);
+        replay.seek(13);
+        assert_eq!(cells(&replay), [10, 4]);
+        assert_eq!(replay.applied, 14);
+    }
+}
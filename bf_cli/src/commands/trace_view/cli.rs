@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{
+    cli::SubCmd,
+    utils::trace::{read_trace, TraceFormat, TRACE_FORMATS},
+};
+
+use super::app::App;
+
+const ABOUT: &str = "Step through a trace recorded with `bf run --trace`";
+const FORMAT_HELP: &str = "The format of the trace file. Defaults to csv \
+                           if the file ends in .csv, otherwise jsonl.";
+const INFILE_HELP: &str = "The trace file to view";
+
+#[derive(Debug, Parser)]
+#[command(about=ABOUT)]
+pub struct TraceViewCli {
+    #[arg(short, long, value_parser=TRACE_FORMATS, help=FORMAT_HELP)]
+    pub format: Option<String>,
+
+    #[arg(help=INFILE_HELP)]
+    pub infile: PathBuf,
+}
+
+impl SubCmd for TraceViewCli {
+    fn run(self) -> Result<()> {
+        let format = TraceFormat::new(self.format.as_deref(), &self.infile)?;
+        let events = read_trace(&self.infile, format)?;
+        App::new(events).and_then(|mut app| app.run())
+    }
+}
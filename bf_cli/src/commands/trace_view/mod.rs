@@ -0,0 +1,5 @@
+pub use self::cli::TraceViewCli;
+
+mod app;
+
+mod cli;
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{
+    cli::{parse_infile, SubCmd},
+    utils::dialect::{parse_dialect, DIALECT_HELP},
+};
+
+use super::subcmd_translate;
+
+const ABOUT: &str = "Translate a script between Brainfuck and dialects that \
+                     use different tokens for the instructions, such as Ook!";
+const FROM_HELP: &str = "The dialect of the script.";
+const TO_HELP: &str = "The dialect to translate the script to.";
+const OUTFILE_HELP: &str = "The file to which the translated script is \
+                            written. If none is given print to stdout.";
+const INFILE_HELP: &str = "The path to the script to translate. Read from \
+                           stdin if infile is a dash (-) or is omitted.";
+
+#[derive(Debug, Parser)]
+#[command(about=ABOUT, after_help=DIALECT_HELP)]
+pub struct TranslateCli {
+    #[arg(
+        short,
+        long,
+        default_value="brainfuck",
+        value_parser=parse_dialect,
+        help=FROM_HELP
+    )]
+    pub from: String,
+
+    #[arg(
+        short,
+        long,
+        default_value="brainfuck",
+        value_parser=parse_dialect,
+        help=TO_HELP
+    )]
+    pub to: String,
+
+    #[arg(short, long, help=OUTFILE_HELP)]
+    pub outfile: Option<PathBuf>,
+
+    #[arg(value_parser=parse_infile, help=INFILE_HELP)]
+    pub infile: Option<PathBuf>,
+}
+
+impl SubCmd for TranslateCli {
+    fn run(self) -> Result<()> {
+        subcmd_translate(self)
+    }
+}
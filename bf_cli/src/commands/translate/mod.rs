@@ -0,0 +1,33 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    err_file_write,
+    utils::{dialect::load_dialect, read::read_script},
+};
+
+pub use self::cli::TranslateCli;
+
+mod cli;
+
+pub fn subcmd_translate(args: TranslateCli) -> Result<()> {
+    let source = read_script(args.infile.as_ref())?;
+    let from = load_dialect(&args.from)?;
+    let to = load_dialect(&args.to)?;
+
+    let translated = to.encode(&from.decode(&source));
+
+    match &args.outfile {
+        Some(path) => {
+            fs::write(path, translated).with_context(|| err_file_write!(path))
+        }
+        None => io::stdout()
+            .write_all(&translated)
+            .with_context(|| err_file_write!(PathBuf::from("STDOUT"))),
+    }
+}
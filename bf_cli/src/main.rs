@@ -1,4 +1,3 @@
-#![feature(try_blocks)]
 #![deny(clippy::cargo)]
 #![warn(clippy::nursery)]
 #![allow(
@@ -38,6 +37,18 @@ enum Cli {
     InputDebug(commands::input_debug::InputDebugCli),
 
     InlineScroll(commands::inline_scroll::InlineScrollCli),
+
+    Cover(commands::cover::CoverCli),
+
+    Equiv(commands::equiv::EquivCli),
+
+    TraceView(commands::trace_view::TraceViewCli),
+
+    Batch(commands::batch::BatchCli),
+
+    Asm(commands::asm::AsmCli),
+
+    Translate(commands::translate::TranslateCli),
 }
 
 impl Cli {
@@ -48,6 +59,12 @@ impl Cli {
             Self::Live(cli) => cli.run(),
             Self::InputDebug(cli) => cli.run(),
             Self::InlineScroll(cli) => cli.run(),
+            Self::Cover(cli) => cli.run(),
+            Self::Equiv(cli) => cli.run(),
+            Self::TraceView(cli) => cli.run(),
+            Self::Batch(cli) => cli.run(),
+            Self::Asm(cli) => cli.run(),
+            Self::Translate(cli) => cli.run(),
         }
     }
 }
@@ -0,0 +1,167 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use bf::{
+    dialect::{Dialect, DIALECTS, INSTRUCTIONS},
+    interpreter::{Extension, Options},
+};
+use serde::Deserialize;
+
+use crate::{cli::ClapError, utils::source_map::SourceMap};
+
+pub const DIALECT_HELP: &str = "One of the built-in dialects brainfuck, ook, \
+                                blub and alphuck, or a TOML file with a \
+                                [tokens] table that maps each instruction to \
+                                its token, e.g. \">\" = \"Ook. Ook?\", and an \
+                                optional separator put between tokens.";
+
+pub const RUN_DIALECT_HELP: &str =
+    "The dialect of the script: pbrain, which adds procedures to Brainfuck, \
+    brainfork, which adds threads forked with Y, boolfuck, which has a tape \
+    of bits, one of the built-in dialects brainfuck, ook, blub and alphuck, \
+    or a TOML file with a [tokens] table that maps each instruction to its \
+    token, e.g. \">\" = \"Ook. Ook?\".";
+
+pub const COMPAT_HELP: &str =
+    "Follow the conventions of published Brainfuck programs: everything \
+    after the first ! in the script is read as input, before any other \
+    input. Otherwise a ! is a comment.";
+
+/// The extensions of Brainfuck that can be given to `--dialect`.
+const EXTENSIONS: [(&str, Extension); 3] = [
+    ("pbrain", Extension::Pbrain),
+    ("brainfork", Extension::Brainfork),
+    ("boolfuck", Extension::Boolfuck),
+];
+
+/// A dialect defined by the user.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DialectFile {
+    #[serde(default)]
+    separator: String,
+    tokens: HashMap<String, String>,
+}
+
+/// Accept the name of a built-in dialect or the path to a dialect file.
+pub fn parse_dialect(value: &str) -> Result<String, ClapError> {
+    if DIALECTS.contains(&value) || Path::new(value).is_file() {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "not a dialect or a file: {value} (dialects: {})",
+            DIALECTS.join(", ")
+        )
+        .into())
+    }
+}
+
+/// Also accept the name of an extension of Brainfuck.
+pub fn parse_run_dialect(value: &str) -> Result<String, ClapError> {
+    if EXTENSIONS.iter().any(|(name, _)| *name == value) {
+        Ok(value.to_string())
+    } else {
+        parse_dialect(value)
+    }
+}
+
+/// How to run a script given to `--dialect`.
+pub struct RunDialect {
+    /// Translate the tokens of the script to Brainfuck. Brainfuck itself is
+    /// left alone so that comments are kept for display.
+    tokens: Option<Dialect>,
+    pub extension: Extension,
+}
+
+impl RunDialect {
+    pub fn load(name: &str) -> Result<Self> {
+        if let Some((_, extension)) = EXTENSIONS.iter().find(|e| e.0 == name) {
+            return Ok(Self {
+                tokens: None,
+                extension: *extension,
+            });
+        }
+        let tokens = match name {
+            "brainfuck" => None,
+            _ => Some(load_dialect(name)?),
+        };
+        Ok(Self {
+            tokens,
+            extension: Extension::None,
+        })
+    }
+
+    /// The options for the interpreter, following [`Options::compat`] if
+    /// `compat`.
+    pub const fn options(&self, compat: bool) -> Options {
+        let options = if compat {
+            Options::compat()
+        } else {
+            Options {
+                extension: Extension::None,
+                embedded_input: false,
+            }
+        };
+        Options {
+            extension: self.extension,
+            ..options
+        }
+    }
+
+    /// Whether the script is written in tokens that are translated to
+    /// Brainfuck, rather than in Brainfuck itself.
+    pub const fn has_tokens(&self) -> bool {
+        self.tokens.is_some()
+    }
+
+    /// Map each instruction of the decoded script back to where it appears
+    /// in `script`, as written in the dialect.
+    pub fn source_map(&self, script: &[u8]) -> SourceMap {
+        match &self.tokens {
+            Some(dialect) => {
+                SourceMap::from_offsets(script, dialect.token_offsets(script))
+            }
+            None => SourceMap::new(
+                &script.split(|&b| b == b'\n').collect::<Vec<_>>(),
+                self.extension,
+            ),
+        }
+    }
+
+    pub fn decode(&self, script: Vec<u8>) -> Vec<u8> {
+        match &self.tokens {
+            Some(dialect) => dialect.decode(&script),
+            None => script,
+        }
+    }
+}
+
+pub fn load_dialect(name: &str) -> Result<Dialect> {
+    if let Some(dialect) = Dialect::named(name) {
+        return Ok(dialect);
+    }
+
+    let path = Path::new(name);
+    let text =
+        fs::read_to_string(path).with_context(|| err_file_read!(path))?;
+    let file = toml::from_str::<DialectFile>(&text)
+        .with_context(|| format!("invalid dialect file: {name}"))?;
+
+    let mut tokens = file.tokens;
+    let mut ordered = Vec::with_capacity(INSTRUCTIONS.len());
+    for instruction in INSTRUCTIONS.map(char::from) {
+        match tokens.remove(&instruction.to_string()) {
+            Some(token) => ordered.push(token),
+            None => bail!("invalid dialect file {name}: no `{instruction}`"),
+        }
+    }
+    if let Some(key) = tokens.keys().next() {
+        bail!("invalid dialect file {name}: `{key}` is not an instruction");
+    }
+
+    Dialect::new(
+        ordered.try_into().expect("one token per instruction"),
+        file.separator,
+    )
+    .with_context(|| format!("invalid dialect file: {name}"))
+}
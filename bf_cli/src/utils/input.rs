@@ -0,0 +1,130 @@
+use anyhow::{bail, Result};
+
+pub const INPUT_FORMATS: [&str; 3] = ["text", "escaped", "hex"];
+
+/// Convert input given on the command line into bytes.
+///
+/// - `text`: the bytes of the text, as is.
+/// - `escaped`: the text with the escape sequences `\n`, `\r`, `\t`, `\0`,
+///   `\\`, and `\xHH` replaced by the bytes they stand for.
+/// - `hex`: pairs of hex digits, optionally separated by whitespace.
+pub fn parse_input(text: &str, format: &str) -> Result<Vec<u8>> {
+    match format {
+        "text" => Ok(text.as_bytes().to_vec()),
+        "escaped" => parse_escaped(text),
+        "hex" => parse_hex(text),
+        _ => bail!("invalid input format (impossible): {format}"),
+    }
+}
+
+fn parse_escaped(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+
+    while let Some(b) = iter.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        let byte = match iter.next() {
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b't') => b'\t',
+            Some(b'0') => b'\0',
+            Some(b'\\') => b'\\',
+            Some(b'x') => {
+                let digits = [iter.next(), iter.next()];
+                let [Some(hi), Some(lo)] = digits else {
+                    bail!("incomplete escape sequence: expected \\xHH");
+                };
+                parse_hex_byte(hi, lo)?
+            }
+            Some(c) => {
+                bail!("invalid escape sequence: \\{}", char::from(c))
+            }
+            None => bail!("incomplete escape sequence at end of input"),
+        };
+        bytes.push(byte);
+    }
+
+    Ok(bytes)
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>> {
+    let digits = text
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    if digits.len() % 2 != 0 {
+        bail!("hex input must have an even number of digits");
+    }
+    digits
+        .chunks_exact(2)
+        .map(|pair| parse_hex_byte(pair[0], pair[1]))
+        .collect()
+}
+
+fn parse_hex_byte(hi: u8, lo: u8) -> Result<u8> {
+    let digit = |b: u8| char::from(b).to_digit(16);
+    match (digit(hi), digit(lo)) {
+        (Some(hi), Some(lo)) => Ok((hi << 4 | lo) as u8),
+        _ => bail!("invalid hex byte: {}", String::from_utf8_lossy(&[hi, lo])),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error(text: &str, format: &str) -> String {
+        parse_input(text, format).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn text_is_kept_as_is() {
+        let bytes = parse_input("a\\n é", "text").unwrap();
+        assert_eq!(bytes, "a\\n é".as_bytes());
+    }
+
+    #[test]
+    fn escapes_are_replaced() {
+        let bytes = parse_input(r"a\n\r\t\0\\\x41\xfF", "escaped").unwrap();
+        assert_eq!(bytes, b"a\n\r\t\0\\A\xff");
+    }
+
+    #[test]
+    fn invalid_escapes_are_errors() {
+        assert_eq!(error(r"\q", "escaped"), "invalid escape sequence: \\q");
+        assert_eq!(
+            error("a\\", "escaped"),
+            "incomplete escape sequence at end of input"
+        );
+        assert_eq!(
+            error(r"\x4", "escaped"),
+            "incomplete escape sequence: expected \\xHH"
+        );
+        assert_eq!(error(r"\xg1", "escaped"), "invalid hex byte: g1");
+    }
+
+    #[test]
+    fn hex_pairs_may_be_separated_by_whitespace() {
+        let bytes = parse_input("00 7f\nFF\t0a", "hex").unwrap();
+        assert_eq!(bytes, [0x00, 0x7f, 0xff, 0x0a]);
+        assert!(parse_input("", "hex").unwrap().is_empty());
+    }
+
+    #[test]
+    fn odd_length_hex_is_an_error() {
+        assert_eq!(
+            error("abc", "hex"),
+            "hex input must have an even number of digits"
+        );
+    }
+
+    #[test]
+    fn invalid_hex_digits_are_errors() {
+        assert_eq!(error("zz", "hex"), "invalid hex byte: zz");
+        assert_eq!(error("+f", "hex"), "invalid hex byte: +f");
+        assert_eq!(error(r"\x-1", "escaped"), "invalid hex byte: -1");
+    }
+}
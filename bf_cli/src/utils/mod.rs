@@ -1 +1,11 @@
+pub mod dialect;
+
+pub mod input;
+
+pub mod palette;
+
 pub mod read;
+
+pub mod source_map;
+
+pub mod trace;
@@ -0,0 +1,72 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::{Context, Result};
+use bf_tui::widgets::live::Palette;
+use ratatui::style::Color;
+use serde::Deserialize;
+
+pub const PALETTE_HELP: &str = "A TOML file with the colors used to \
+                                highlight the script: arithmetic (+-), \
+                                movement (<>), loops ([]), io (.,), \
+                                extension, comments and line_numbers, and \
+                                the backgrounds of matching and unmatched \
+                                brackets, e.g. loops = \"#ff8800\". Each is \
+                                a color name, an index from 0 to 255, or a \
+                                hex color, and any that are left out keep \
+                                their default. Comments are always dimmed.";
+
+/// The colors of a palette set by the user.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PaletteFile {
+    arithmetic: Option<String>,
+    movement: Option<String>,
+    loops: Option<String>,
+    io: Option<String>,
+    extension: Option<String>,
+    comments: Option<String>,
+    line_numbers: Option<String>,
+    matching: Option<String>,
+    unmatched: Option<String>,
+}
+
+pub fn load_palette(path: &Path) -> Result<Palette> {
+    let text =
+        fs::read_to_string(path).with_context(|| err_file_read!(path))?;
+    let file = toml::from_str::<PaletteFile>(&text).with_context(|| {
+        format!("invalid palette file: {}", path.display())
+    })?;
+
+    let mut palette = Palette::default();
+    let fields = [
+        (file.arithmetic, &mut palette.arithmetic),
+        (file.movement, &mut palette.movement),
+        (file.loops, &mut palette.loops),
+        (file.io, &mut palette.io),
+        (file.extension, &mut palette.extension),
+        (file.comments, &mut palette.comments),
+        (file.line_numbers, &mut palette.line_numbers),
+    ];
+    let parse = |color: String| {
+        Color::from_str(&color).with_context(|| {
+            format!(
+                "invalid palette file {}: not a color: {color}",
+                path.display()
+            )
+        })
+    };
+    for (color, style) in fields {
+        if let Some(color) = color {
+            *style = style.fg(parse(color)?);
+        }
+    }
+    for (color, style) in [
+        (file.matching, &mut palette.matching),
+        (file.unmatched, &mut palette.unmatched),
+    ] {
+        if let Some(color) = color {
+            *style = style.bg(parse(color)?);
+        }
+    }
+    Ok(palette)
+}
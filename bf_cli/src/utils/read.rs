@@ -1,29 +1,37 @@
 use std::{
-    fs::File,
-    io::{self, BufRead, BufReader},
-    path::PathBuf,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Result};
 
-pub fn read_script(infile: Option<&PathBuf>) -> Result<Vec<String>> {
+/// Read the script byte for byte, so that scripts which aren't valid UTF-8
+/// can still be executed.
+pub fn read_script(infile: Option<&PathBuf>) -> Result<Vec<u8>> {
     match infile {
-        Some(path) if *path != PathBuf::from("-") => read_script_file(path),
+        Some(path) if path.as_os_str() != "-" => read_script_file(path),
         _ => read_script_stdin(),
     }
 }
 
-fn read_script_stdin() -> Result<Vec<String>> {
+fn read_script_stdin() -> Result<Vec<u8>> {
+    let mut script = Vec::new();
     io::stdin()
-        .lines()
-        .collect::<Result<_, _>>()
-        .with_context(|| err_file_read!(PathBuf::from("STDIN")))
+        .read_to_end(&mut script)
+        .with_context(|| err_file_read!(PathBuf::from("STDIN")))?;
+    Ok(script)
+}
+
+pub fn read_script_file(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| err_file_read!(path))
 }
 
-pub fn read_script_file(path: &PathBuf) -> Result<Vec<String>> {
-    let file = File::open(path).with_context(|| err_file_open!(path))?;
-    BufReader::new(file)
+/// Split the script into lines for display. Invalid UTF-8 is replaced, which
+/// never affects the instructions since they are all ASCII.
+pub fn script_lines(script: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(script)
         .lines()
-        .collect::<Result<_, _>>()
-        .with_context(|| err_file_read!(path))
+        .map(String::from)
+        .collect()
 }
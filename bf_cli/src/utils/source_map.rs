@@ -0,0 +1,65 @@
+use bf::interpreter::Extension;
+
+/// The zero-based position of an instruction in the script source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourcePosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Maps the index of each instruction, as executed by the interpreter, back
+/// to where it appears in the script.
+pub struct SourceMap(Vec<SourcePosition>);
+
+impl SourceMap {
+    pub fn new<L: AsRef<[u8]>>(lines: &[L], extension: Extension) -> Self {
+        let positions = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line, text)| {
+                text.as_ref()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, b)| extension.is_instruction(**b))
+                    .map(move |(column, _)| SourcePosition { line, column })
+            })
+            .collect();
+        Self(positions)
+    }
+
+    /// Map each instruction translated from a token back to where the token
+    /// starts in `source`, given the `offsets` of the tokens in order, see
+    /// [`bf::dialect::Dialect::token_offsets`].
+    pub fn from_offsets(
+        source: &[u8],
+        offsets: impl Iterator<Item = usize>,
+    ) -> Self {
+        let mut line = 0;
+        let mut line_start = 0;
+        let mut scanned = 0;
+        let positions = offsets
+            .map(|offset| {
+                for (i, &b) in source[scanned..offset].iter().enumerate() {
+                    if b == b'\n' {
+                        line += 1;
+                        line_start = scanned + i + 1;
+                    }
+                }
+                scanned = offset;
+                SourcePosition {
+                    line,
+                    column: offset - line_start,
+                }
+            })
+            .collect();
+        Self(positions)
+    }
+
+    pub fn get(&self, ip: usize) -> Option<SourcePosition> {
+        self.0.get(ip).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = SourcePosition> + '_ {
+        self.0.iter().copied()
+    }
+}
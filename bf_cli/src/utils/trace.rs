@@ -0,0 +1,112 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub const TRACE_FORMATS: [&str; 2] = ["jsonl", "csv"];
+
+/// One executed instruction. Source positions are one-based, and `before`
+/// and `after` are the values of the cell at `pointer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceEvent {
+    pub step: u64,
+    pub ip: usize,
+    pub line: usize,
+    pub column: usize,
+    pub instruction: char,
+    pub pointer: usize,
+    pub before: u8,
+    pub after: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    Jsonl,
+    Csv,
+}
+
+impl TraceFormat {
+    /// Pick the format by name, falling back to the file extension.
+    pub fn new(name: Option<&str>, path: &Path) -> Result<Self> {
+        let name = name.unwrap_or_else(|| {
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("csv") => "csv",
+                _ => "jsonl",
+            }
+        });
+        match name {
+            "jsonl" => Ok(Self::Jsonl),
+            "csv" => Ok(Self::Csv),
+            _ => bail!("invalid trace format (impossible): {name}"),
+        }
+    }
+}
+
+enum TraceSink {
+    Jsonl(BufWriter<File>),
+    Csv(Box<csv::Writer<File>>),
+}
+
+pub struct TraceWriter {
+    path: PathBuf,
+    sink: TraceSink,
+}
+
+impl TraceWriter {
+    pub fn create(path: &Path, format: TraceFormat) -> Result<Self> {
+        let file = File::create(path).with_context(|| err_file_open!(path))?;
+        let sink = match format {
+            TraceFormat::Jsonl => TraceSink::Jsonl(BufWriter::new(file)),
+            TraceFormat::Csv => {
+                TraceSink::Csv(Box::new(csv::Writer::from_writer(file)))
+            }
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            sink,
+        })
+    }
+
+    pub fn write(&mut self, event: &TraceEvent) -> Result<()> {
+        let res = match &mut self.sink {
+            TraceSink::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, event)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|()| Ok(writer.write_all(b"\n")?))
+            }
+            TraceSink::Csv(writer) => Ok(writer.serialize(event)?),
+        };
+        res.with_context(|| err_file_write!(self.path))
+    }
+
+    pub fn finish(self) -> Result<()> {
+        let res: Result<()> = match self.sink {
+            TraceSink::Jsonl(mut writer) => writer.flush().map_err(Into::into),
+            TraceSink::Csv(mut writer) => writer.flush().map_err(Into::into),
+        };
+        res.with_context(|| err_file_write!(self.path))
+    }
+}
+
+pub fn read_trace(
+    path: &Path,
+    format: TraceFormat,
+) -> Result<Vec<TraceEvent>> {
+    let file = File::open(path).with_context(|| err_file_open!(path))?;
+    match format {
+        TraceFormat::Jsonl => BufReader::new(file)
+            .lines()
+            .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect::<Result<_>>(),
+        TraceFormat::Csv => csv::Reader::from_reader(file)
+            .deserialize()
+            .map(|event| Ok(event?))
+            .collect::<Result<_>>(),
+    }
+    .with_context(|| err_file_read!(path))
+}
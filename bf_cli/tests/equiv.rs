@@ -0,0 +1,65 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{self, Command, Output},
+};
+
+/// Write `contents` to a file in the temporary directory.
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let path =
+        env::temp_dir().join(format!("bf-equiv-{}-{name}", process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Run `bf equiv` on the scripts `left` and `right`, with `input` as the
+/// only input.
+fn bf_equiv(name: &str, left: &str, right: &str, input: &str) -> Output {
+    let left = temp_file(&format!("{name}-left.b"), left);
+    let right = temp_file(&format!("{name}-right.b"), right);
+    let input = temp_file(&format!("{name}-input"), input);
+    let output = Command::new(env!("CARGO_BIN_EXE_bf_cli"))
+        .arg("equiv")
+        .args(["--random", "0", "--input"])
+        .args([&input, &left, &right])
+        .output()
+        .unwrap();
+    for path in [left, right, input] {
+        fs::remove_file(path).ok();
+    }
+    output
+}
+
+#[test]
+fn reports_where_scripts_diverge() {
+    // The right script adds 1 to each byte
+    let output = bf_equiv("diverge", ",[.,]", ",[+.,]", "ab");
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4, "{stdout}");
+    assert!(lines[0].starts_with("The scripts differ on "), "{stdout}");
+    assert!(lines[0].ends_with("-diverge-input: \"ab\""), "{stdout}");
+    assert!(lines[1].ends_with(
+        "-diverge-left.b: finished after 8 steps, 2 bytes of output"
+    ));
+    assert!(lines[2].ends_with(
+        "-diverge-right.b: finished after 10 steps, 2 bytes of output"
+    ));
+    assert_eq!(lines[3], "  outputs differ at byte 0: 0x61 (a) vs 0x62 (b)");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("the scripts are not equivalent"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn agreeing_scripts_succeed() {
+    // Comments and a cancelled instruction do not change what it does
+    let output = bf_equiv("agree", ",[.,]", "cat: ,[.+-,]", "ab");
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, b"The scripts agree on 1 of 1 inputs\n");
+}
@@ -0,0 +1,155 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// Run `bf run` with `args` on `script`, given on stdin.
+fn bf_run(args: &[&str], script: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bf_cli"))
+        .arg("run")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn streams_output() {
+    let output = bf_run(&["--raw"], "++++++++[>++++++++<-]>+.+.");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"AB");
+}
+
+#[test]
+fn streams_boolfuck_output() {
+    // A full byte written with `;` and a partial one padded at the end
+    let script = "+;;+;;;;;;+;";
+    let output = bf_run(&["--raw", "--dialect", "boolfuck"], script);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"\x03\x01");
+}
+
+#[test]
+fn bang_is_a_comment_by_default() {
+    let script = "+++[>++++++++++<-]>+++.!\n+++.";
+    let output = bf_run(&["--raw"], script);
+    assert_eq!(output.stdout, b"!$");
+}
+
+#[test]
+fn compat_reads_input_after_bang() {
+    let output = bf_run(&["--raw", "--compat", "-i", "c"], ",[.,]!ab");
+    assert_eq!(output.stdout, b"abc");
+}
+
+#[test]
+fn report_is_kept_apart_from_the_output() {
+    let output = bf_run(&["--raw", "--report", "json"], "+++.>+");
+    assert_eq!(output.stdout, b"\x03");
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(report["exit_reason"], "done");
+    assert_eq!(report["steps"], 6);
+    assert_eq!(report["tape"], serde_json::json!([3, 1]));
+    assert_eq!(report["output_base64"], "Aw==");
+}
+
+#[test]
+fn report_includes_the_error() {
+    let output = bf_run(&["--raw", "--report", "toml"], "+]");
+    assert_eq!(output.status.code(), Some(3));
+    let report: toml::Table =
+        toml::from_str(std::str::from_utf8(&output.stderr).unwrap()).unwrap();
+    assert_eq!(report["exit_reason"].as_str(), Some("error"));
+    assert!(report.contains_key("error"));
+}
+
+#[test]
+fn pbrain_errors_have_their_own_exit_codes() {
+    let pbrain = ["--raw", "--dialect", "pbrain"];
+    assert_eq!(bf_run(&pbrain, "+:").status.code(), Some(6));
+    assert_eq!(bf_run(&pbrain, "(:):").status.code(), Some(7));
+}
+
+#[test]
+fn show_tape_warns_when_stdout_is_not_a_terminal() {
+    let output = bf_run(&["--show-tape"], "+++.");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"\x03");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("ignoring -s/--show-tape"), "{stderr}");
+}
+
+#[test]
+fn show_tape_conflicts_with_raw() {
+    let output = bf_run(&["--show-tape", "--raw"], "+++.");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn plain_run_reports_errors() {
+    let output = bf_run(&[], "+.]");
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(output.stdout, b"\x01");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Error: mismatched brackets"), "{stderr}");
+}
+
+#[test]
+fn trace_brainfork_after_a_thread_finishes() {
+    let path = std::env::temp_dir()
+        .join(format!("bf-run-trace-{}.jsonl", std::process::id()));
+    let path_arg = path.to_string_lossy();
+    let args = ["--raw", "--dialect", "brainfork", "--trace", &path_arg];
+    let output = bf_run(&args, "Y[+++[-]]");
+    assert!(output.status.success(), "{output:?}");
+
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    let steps = trace
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    // Y and the parent's skipped loop, then the child's loops, which clear
+    // its cell from 4
+    assert_eq!(steps.len(), 2 + 1 + 3 + 1 + 4 * 2 + 1);
+    assert!(steps.iter().all(|step| step["ip"].as_u64() < Some(9)));
+}
+
+#[test]
+fn trace_positions_of_tokens() {
+    let path = std::env::temp_dir()
+        .join(format!("bf-run-trace-ook-{}.jsonl", std::process::id()));
+    let path_arg = path.to_string_lossy();
+    let args = ["--raw", "--dialect", "ook", "--trace", &path_arg];
+    // The last token, `.`, is split across lines
+    let output = bf_run(&args, "Ook. Ook.\n  Ook. Ook. Ook!\nOok.");
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, [2]);
+
+    let trace = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    let positions = trace
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .map(|step| (step["line"].clone(), step["column"].clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        positions,
+        [
+            (1.into(), 1.into()),
+            (2.into(), 3.into()),
+            (2.into(), 13.into())
+        ]
+    );
+}
@@ -6,24 +6,19 @@ use std::{
 };
 
 use anyhow::{bail, Result};
-use bf::interpreter::{Interpreter, Tape};
+use bf::interpreter::{Interpreter, Options, Tape};
 use bf_utils::sync::{SharedBool, SharedCell};
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Default)]
 pub enum Status {
     Running,
     WaitingForInput,
+    #[default]
     Done,
     Error(String),
     FatalError(String),
 }
 
-impl Default for Status {
-    fn default() -> Self {
-        Self::Done
-    }
-}
-
 impl Display for Status {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
@@ -39,6 +34,8 @@ impl Display for Status {
 pub struct State {
     pub status: Status,
     pub tape: Tape,
+    /// The cursor of each thread, starting with the one that is running.
+    pub cursors: Vec<usize>,
     pub output: Vec<u8>,
 }
 
@@ -57,6 +54,7 @@ impl AsyncInterpreter {
         code: Vec<u8>,
         input: VecDeque<u8>,
         auto_input: Option<u8>,
+        options: Options,
     ) -> Self {
         let this = Self {
             stop: SharedBool::new(false),
@@ -70,7 +68,12 @@ impl AsyncInterpreter {
             let mut int = if let Some((code, input, auto_input)) =
                 shared.program.load()
             {
-                Interpreter::new(code.into_iter(), input, auto_input)
+                Interpreter::with_options(
+                    code.into_iter(),
+                    input,
+                    auto_input,
+                    options,
+                )
             } else {
                 thread::yield_now();
                 shared.restart_barrier.wait();
@@ -81,6 +84,7 @@ impl AsyncInterpreter {
                 shared.state.store(State {
                     status,
                     tape: int.tape.clone(),
+                    cursors: int.cursors(),
                     output: int.output.clone(),
                 });
             };
@@ -88,11 +92,8 @@ impl AsyncInterpreter {
             shared.stop.store(false);
 
             while !shared.stop.load() {
-                match int.peek() {
-                    Some(',') if int.input.is_empty() => {
-                        set_state(Status::WaitingForInput, &int);
-                    }
-                    _ => (),
+                if int.needs_input() {
+                    set_state(Status::WaitingForInput, &int);
                 }
                 match int.next() {
                     None => {
@@ -137,6 +138,7 @@ impl AsyncInterpreter {
             None => State {
                 status: Status::FatalError(ERROR_POISONED.into()),
                 tape: Tape::default(),
+                cursors: Vec::new(),
                 output: Vec::new(),
             },
         }
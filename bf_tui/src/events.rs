@@ -6,6 +6,7 @@ use std::{
     time::Duration,
 };
 
+use bf_utils::sync::SharedU64;
 use crossterm::event::{
     self, Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers,
 };
@@ -75,6 +76,7 @@ type EventQueueBuf = VecDeque<BfEvent>;
 #[derive(Clone)]
 pub struct EventQueue {
     data: Arc<Mutex<EventQueueBuf>>,
+    tick_delay: SharedU64,
 }
 
 impl EventQueue {
@@ -82,6 +84,7 @@ impl EventQueue {
     pub fn new() -> Self {
         let this = Self {
             data: Arc::default(),
+            tick_delay: SharedU64::new(0),
         };
 
         {
@@ -106,18 +109,36 @@ impl EventQueue {
 
     pub fn with_ticks(delay_ms: u64) -> Self {
         let this = Self::new();
+        this.set_tick_delay(delay_ms);
 
         {
             let this = this.clone();
             thread::spawn(move || loop {
                 this.safe_mutate(|mut q| q.push_back(BfEvent::Tick));
-                thread::sleep(Duration::from_millis(delay_ms));
+                thread::sleep(Duration::from_millis(this.tick_delay()));
             });
         }
 
         this
     }
 
+    /// A queue that nothing feeds, e.g. to drive an app from tests.
+    pub fn detached(delay_ms: u64) -> Self {
+        Self {
+            data: Arc::default(),
+            tick_delay: SharedU64::new(delay_ms),
+        }
+    }
+
+    pub fn tick_delay(&self) -> u64 {
+        self.tick_delay.load()
+    }
+
+    /// Change the delay between ticks, taking effect after the next tick.
+    pub fn set_tick_delay(&self, delay_ms: u64) {
+        self.tick_delay.store(delay_ms);
+    }
+
     pub fn pop_all(&self) -> VecDeque<BfEvent> {
         let mut events: Vec<BfEvent> =
             self.safe_mutate(|mut q| q.drain(..).collect());
@@ -8,12 +8,9 @@ use ratatui::{
     widgets::{Paragraph, Widget},
 };
 
-use crate::{
-    lines::{
-        TapeBorderHorizontal, TAPE_BORDER_SET, TAPE_HORIZONTAL_BORDER_BOTTOM,
-        TAPE_HORIZONTAL_BORDER_TOP,
-    },
-    sublayouts,
+use crate::lines::{
+    TapeBorderHorizontal, TAPE_BORDER_SET, TAPE_HORIZONTAL_BORDER_BOTTOM,
+    TAPE_HORIZONTAL_BORDER_TOP,
 };
 
 #[derive(Default)]
@@ -23,6 +20,8 @@ pub struct CellWidget {
     pub right_border_cap: Option<bool>,
     pub is_highlighted: bool,
     pub ascii: bool,
+    /// Show the cell as a bit, 0 or 1, even when `ascii` is set.
+    pub bit: bool,
 }
 
 impl CellWidget {
@@ -41,13 +40,15 @@ impl CellWidget {
         self.display_horizontal_edge(TAPE_HORIZONTAL_BORDER_BOTTOM)
     }
 
-    pub fn display_value(&self) -> Cow<str> {
+    pub fn display_value(&self) -> Cow<'_, str> {
         macro_rules! owned {
             ($value:expr) => {
                 Cow::Owned(format!("{:^3}", $value))
             };
         }
-        if self.ascii {
+        if self.bit {
+            Cow::Borrowed(if self.value == 0 { " 0 " } else { " 1 " })
+        } else if self.ascii {
             let c = self.value as char;
             match c {
                 '\0' => Cow::Borrowed(r"\0 "),
@@ -189,6 +190,16 @@ mod test {
         assert_eq!(widget.display_value(), expected_str);
     }
 
+    #[test_case(0, " 0 " ; "0")]
+    #[test_case(1, " 1 " ; "1")]
+    fn display_bit_value(value: u8, expected_str: &str) {
+        let widget = CellWidget {
+            bit: true,
+            ..ascii_cell_with_value(value)
+        };
+        assert_eq!(widget.display_value(), expected_str);
+    }
+
     #[test]
     fn render_left_cap() {
         let mut term = terminal_for_cell();
@@ -1,4 +1,3 @@
-use bf::interpreter::Tape;
 use ratatui::{
     prelude::{Buffer, Constraint, Layout, Rect},
     style::Stylize,
@@ -10,16 +9,15 @@ use crate::{
     async_interpreter::{
         State as InterpreterState, Status as InterpreterStatus,
     },
-    sublayouts,
     widgets::{Spinner, VerticalStack},
 };
 
 use super::{
     dialogs::{centered_rect, Dialog},
-    Footer, Header, TapeViewport, TapeViewportState,
+    Editor, EditorState, Footer, Header, TapeViewport, TapeViewportState,
 };
 
-pub struct AppWidget<'app, 'textarea, Editor: Widget> {
+pub struct AppWidget<'app, 'textarea> {
     pub term_width: usize,
     pub term_height: usize,
     pub dialog: Option<&'app Dialog<'textarea>>,
@@ -28,10 +26,11 @@ pub struct AppWidget<'app, 'textarea, Editor: Widget> {
     pub spinner: Spinner,
     pub async_interpreter: InterpreterState,
     pub tape_viewport: TapeViewportState,
-    pub editor: Editor,
+    pub editor: Editor<'app>,
+    pub editor_state: &'app mut EditorState,
 }
 
-impl<Editor: Widget> Widget for AppWidget<'_, '_, Editor> {
+impl Widget for AppWidget<'_, '_> {
     fn render(mut self, area: Rect, buf: &mut Buffer) {
         let layout = Layout::vertical(vec![
             Constraint::Length(1),
@@ -46,17 +45,17 @@ impl<Editor: Widget> Widget for AppWidget<'_, '_, Editor> {
             buf,
             self.is_dirty,
             self.file_path,
-            self.async_interpreter.status,
+            self.async_interpreter.status.clone(),
             self.spinner,
         );
 
         draw_content(
             content_area,
             buf,
-            &self.async_interpreter.tape,
+            &self.async_interpreter,
             &mut self.tape_viewport,
             self.editor,
-            &self.async_interpreter.output,
+            self.editor_state,
         );
 
         draw_footer(footer_area, buf);
@@ -87,12 +86,12 @@ fn draw_header(
 fn draw_content(
     area: Rect,
     buf: &mut Buffer,
-    tape: &Tape,
+    interpreter: &InterpreterState,
     tape_state: &mut TapeViewportState,
-    editor: impl Widget,
-    output: &[u8],
+    editor: Editor,
+    editor_state: &mut EditorState,
 ) {
-    let output = String::from_utf8_lossy(output);
+    let output = String::from_utf8_lossy(&interpreter.output);
     let output_lines = output.split_terminator('\n').count() as u16;
 
     let tape_title = Line::raw(" Tape ");
@@ -118,10 +117,12 @@ fn draw_content(
     stack.render(area, buf);
 
     // Tape
-    TapeViewport::new(tape).render(tape_area, buf, tape_state);
+    TapeViewport::new(&interpreter.tape)
+        .cursors(&interpreter.cursors)
+        .render(tape_area, buf, tape_state);
 
     // Editor
-    editor.render(editor_area, buf);
+    editor.render(editor_area, buf, editor_state);
 
     // Output
     if !output.is_empty() {
@@ -0,0 +1,431 @@
+use bf::interpreter::Extension;
+use ratatui::{
+    prelude::{Buffer, Rect},
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::StatefulWidget,
+};
+use tui_textarea::TextArea;
+
+use super::TextAreaExts;
+
+/// The number of columns between tab stops.
+const TAB_LEN: usize = 4;
+
+/// The styles used to highlight the script in the live editor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    /// `+` and `-`
+    pub arithmetic: Style,
+    /// `<` and `>`
+    pub movement: Style,
+    /// `[` and `]`
+    pub loops: Style,
+    /// `.` and `,`, and `;` in Boolfuck
+    pub io: Style,
+    /// The other instructions of an extension, such as `Y` in Brainfork.
+    pub extension: Style,
+    /// Everything that is not an instruction.
+    pub comments: Style,
+    pub line_numbers: Style,
+    /// Added to the partner of the bracket under the cursor.
+    pub matching: Style,
+    /// Added to brackets without a partner and to the sign in the gutter of
+    /// their lines.
+    pub unmatched: Style,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            arithmetic: Style::new().fg(Color::Green),
+            movement: Style::new().fg(Color::Blue),
+            loops: Style::new().fg(Color::Magenta),
+            io: Style::new().fg(Color::Cyan),
+            extension: Style::new().fg(Color::LightRed),
+            comments: Style::new().add_modifier(Modifier::DIM),
+            line_numbers: Style::new().fg(Color::Yellow),
+            matching: Style::new()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+            unmatched: Style::new()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+impl Palette {
+    /// No highlighting, for scripts whose characters are not instructions on
+    /// their own, such as those written in Ook!.
+    pub const fn plain() -> Self {
+        Self {
+            arithmetic: Style::new(),
+            movement: Style::new(),
+            loops: Style::new(),
+            io: Style::new(),
+            extension: Style::new(),
+            comments: Style::new(),
+            line_numbers: Style::new().fg(Color::Yellow),
+            matching: Style::new(),
+            unmatched: Style::new(),
+        }
+    }
+
+    pub const fn style(&self, c: char, extension: Extension) -> Style {
+        if !c.is_ascii() || !extension.is_instruction(c as u8) {
+            return self.comments;
+        }
+        match c {
+            '+' | '-' => self.arithmetic,
+            '<' | '>' => self.movement,
+            '[' | ']' => self.loops,
+            '.' | ',' | ';' => self.io,
+            _ => self.extension,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EditorState {
+    /// The first visible line.
+    pub top_row: usize,
+    /// The first visible column of the text after the line numbers.
+    pub top_col: usize,
+}
+
+/// The script being edited, with line numbers and each character highlighted
+/// by the palette.
+pub struct Editor<'a> {
+    textarea: &'a TextArea<'a>,
+    palette: &'a Palette,
+    extension: Extension,
+    match_brackets: bool,
+    embedded_input: bool,
+}
+
+impl<'a> Editor<'a> {
+    pub const fn new(
+        textarea: &'a TextArea<'a>,
+        palette: &'a Palette,
+        extension: Extension,
+    ) -> Self {
+        Self {
+            textarea,
+            palette,
+            extension,
+            match_brackets: true,
+            embedded_input: false,
+        }
+    }
+
+    /// Whether to highlight the partner of the bracket under the cursor and
+    /// brackets without a partner. Defaults to true.
+    pub const fn match_brackets(mut self, match_brackets: bool) -> Self {
+        self.match_brackets = match_brackets;
+        self
+    }
+
+    /// Whether everything after the first `!` is the input of the script,
+    /// highlighted as a comment. Defaults to false.
+    pub const fn embedded_input(mut self, embedded_input: bool) -> Self {
+        self.embedded_input = embedded_input;
+        self
+    }
+}
+
+/// Scroll as little as possible to keep the cursor in view.
+const fn scroll_top(top: usize, cursor: usize, len: usize) -> usize {
+    if cursor < top {
+        cursor
+    } else if top + len <= cursor {
+        cursor + 1 - len
+    } else {
+        top
+    }
+}
+
+/// The column of each character of a line, with tabs expanded.
+fn columns(line: &str) -> impl Iterator<Item = (usize, char, usize)> + '_ {
+    line.chars().scan(0, |col, c| {
+        let width = if c == '\t' {
+            TAB_LEN - *col % TAB_LEN
+        } else {
+            Span::raw(c.to_string()).width()
+        };
+        let start = *col;
+        *col += width;
+        Some((start, c, width))
+    })
+}
+
+/// The number of columns taken by a line, with tabs expanded.
+fn width(line: &str) -> usize {
+    columns(line).last().map_or(0, |(col, _, width)| col + width)
+}
+
+impl StatefulWidget for Editor<'_> {
+    type State = EditorState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let lines = self.textarea.lines();
+        let (cursor_row, cursor_col) = self.textarea.cursor();
+        let lnum_len = lines.len().max(1).to_string().len();
+        let gutter = lnum_len + 2;
+        let text_width = usize::from(area.width).saturating_sub(gutter);
+        if text_width == 0 || area.height == 0 {
+            return;
+        }
+
+        // The cursor may be just after the last character
+        let cursor_line = lines.get(cursor_row).map_or("", String::as_str);
+        let cursor_x = columns(cursor_line)
+            .nth(cursor_col)
+            .map_or_else(|| width(cursor_line), |(col, ..)| col);
+        state.top_row =
+            scroll_top(state.top_row, cursor_row, area.height.into());
+        state.top_col = scroll_top(state.top_col, cursor_x, text_width);
+
+        let (partner, unmatched) = if self.match_brackets {
+            let brackets = self.textarea.brackets(self.embedded_input);
+            let partner = brackets.partner((cursor_row, cursor_col));
+            (partner, brackets.unmatched)
+        } else {
+            (None, Vec::new())
+        };
+
+        let input_start = self
+            .embedded_input
+            .then(|| self.textarea.embedded_input_start())
+            .flatten();
+
+        let visible = lines.iter().enumerate().skip(state.top_row);
+        for (y, (row, line)) in (area.y..area.bottom()).zip(visible) {
+            buf.set_string(
+                area.x,
+                y,
+                format!(" {:>lnum_len$} ", row + 1),
+                self.palette.line_numbers,
+            );
+            if unmatched.iter().any(|(r, _)| *r == row) {
+                buf.set_string(area.x, y, "!", self.palette.unmatched);
+            }
+
+            let mut end = 0;
+            for (i, (col, c, width)) in columns(line).enumerate() {
+                end = col + width;
+                if col < state.top_col || end > state.top_col + text_width {
+                    continue;
+                }
+                let mut style = if input_start.is_some_and(|s| (row, i) >= s) {
+                    self.palette.comments
+                } else {
+                    self.palette.style(c, self.extension)
+                };
+                if unmatched.contains(&(row, i)) {
+                    style = style.patch(self.palette.unmatched);
+                } else if partner == Some((row, i)) {
+                    style = style.patch(self.palette.matching);
+                }
+                if (row, col) == (cursor_row, cursor_x) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                let x = area.x + (gutter + col - state.top_col) as u16;
+                let symbol = if c == '\t' {
+                    " ".repeat(width)
+                } else {
+                    c.to_string()
+                };
+                buf.set_string(x, y, symbol, style);
+            }
+
+            // The cursor is after the last character of the line
+            if row == cursor_row
+                && end == cursor_x
+                && (state.top_col..state.top_col + text_width).contains(&end)
+            {
+                let x = area.x + (gutter + end - state.top_col) as u16;
+                let style = Style::new().add_modifier(Modifier::REVERSED);
+                buf.set_string(x, y, " ", style);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bf::interpreter::Extension;
+    use ratatui::{
+        buffer::Buffer,
+        layout::Rect,
+        style::{Color, Modifier},
+    };
+    use tui_textarea::{CursorMove, TextArea};
+
+    use crate::test_utils::terminal;
+
+    use super::{Editor, EditorState, Palette, TextAreaExts};
+
+    fn render(
+        textarea: &TextArea,
+        extension: Extension,
+        width: u16,
+        height: u16,
+        state: &mut EditorState,
+    ) -> Buffer {
+        let palette = Palette::default();
+        let mut term = terminal(width, height);
+        let widget = Editor::new(textarea, &palette, extension);
+        term.draw(|f| f.render_stateful_widget(widget, f.area(), state))
+            .unwrap();
+        term.backend().buffer().clone()
+    }
+
+    #[test]
+    fn highlights_each_kind_of_character() {
+        let textarea = TextArea::from(["+<[.]x"]);
+        let buf = render(
+            &textarea,
+            Extension::None,
+            10,
+            1,
+            &mut EditorState::default(),
+        );
+
+        let palette = Palette::default();
+        let mut expected = Buffer::with_lines([" 1 +<[.]x "]);
+        expected.set_style(Rect::new(0, 0, 3, 1), palette.line_numbers);
+        let cursor = palette.arithmetic.add_modifier(Modifier::REVERSED);
+        for (x, style) in [
+            (3, cursor),
+            (4, palette.movement),
+            (5, palette.loops),
+            (6, palette.io),
+            (7, palette.loops),
+            (8, palette.comments),
+        ] {
+            expected[(x, 0)].set_style(style);
+        }
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn extension_instructions() {
+        let palette = Palette::default();
+        let style = |c| palette.style(c, Extension::Pbrain);
+        assert_eq!(style(':'), palette.extension);
+        assert_eq!(palette.style(':', Extension::None), palette.comments);
+        let style = |c| palette.style(c, Extension::Boolfuck);
+        assert_eq!(style(';'), palette.io);
+        assert_eq!(style('-'), palette.comments);
+    }
+
+    #[test]
+    fn scrolls_to_the_cursor() {
+        let mut textarea = TextArea::from(["+", "-", "<", ">"]);
+        textarea.move_cursor(CursorMove::Bottom);
+        textarea.move_cursor(CursorMove::End);
+        let mut state = EditorState::default();
+        let buf = render(&textarea, Extension::None, 6, 2, &mut state);
+
+        assert_eq!(state.top_row, 2);
+        assert_eq!(buf[(3, 0)].symbol(), "<");
+        assert_eq!(buf[(3, 1)].symbol(), ">");
+        // The cursor is after the end of the last line
+        assert!(buf[(4, 1)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn highlights_the_matching_bracket() {
+        let textarea = TextArea::from(["[-", "]"]);
+        let buf = render(
+            &textarea,
+            Extension::None,
+            6,
+            2,
+            &mut EditorState::default(),
+        );
+
+        let palette = Palette::default();
+        assert_eq!(buf[(3, 1)].bg, palette.matching.bg.unwrap());
+        assert_eq!(buf[(4, 0)].bg, Color::Reset);
+    }
+
+    #[test]
+    fn marks_unmatched_brackets() {
+        let textarea = TextArea::from(["+[]", "]-", "["]);
+        let buf = render(
+            &textarea,
+            Extension::None,
+            8,
+            3,
+            &mut EditorState::default(),
+        );
+
+        let palette = Palette::default();
+        let unmatched = palette.unmatched.bg.unwrap();
+        for (x, y, marked) in [(4, 0, false), (3, 1, true), (3, 2, true)] {
+            assert_eq!(buf[(x, y)].bg == unmatched, marked, "({x}, {y})");
+        }
+        // The sign in the gutter
+        assert_eq!(buf[(0, 0)].symbol(), " ");
+        assert_eq!(buf[(0, 1)].symbol(), "!");
+        assert_eq!(buf[(0, 1)].bg, unmatched);
+        assert_eq!(buf[(0, 2)].symbol(), "!");
+    }
+
+    #[test]
+    fn jumps_to_the_matching_bracket() {
+        let mut textarea = TextArea::from(["+[", "-]"]);
+        textarea.move_cursor(CursorMove::Jump(1, 1));
+        textarea.jump_to_matching_bracket(false);
+        assert_eq!(textarea.cursor(), (0, 1));
+        textarea.jump_to_matching_bracket(false);
+        assert_eq!(textarea.cursor(), (1, 1));
+        // Nothing happens away from a bracket
+        textarea.move_cursor(CursorMove::Head);
+        textarea.jump_to_matching_bracket(false);
+        assert_eq!(textarea.cursor(), (1, 0));
+    }
+
+    #[test]
+    fn embedded_input_is_a_comment() {
+        let textarea = TextArea::from(["+!", "+."]);
+        let palette = Palette::default();
+        let mut term = terminal(8, 2);
+        let widget = Editor::new(&textarea, &palette, Extension::None)
+            .embedded_input(true);
+        let mut state = EditorState::default();
+        term.draw(|f| f.render_stateful_widget(widget, f.area(), &mut state))
+            .unwrap();
+        let buf = term.backend().buffer();
+
+        assert_eq!(buf[(3, 0)].fg, palette.arithmetic.fg.unwrap());
+        for x in 3..5 {
+            assert!(buf[(x, 1)].modifier.contains(Modifier::DIM), "{x}");
+        }
+    }
+
+    #[test]
+    fn brackets_stop_at_embedded_input() {
+        let textarea = TextArea::from(["[!]"]);
+        assert_eq!(textarea.brackets(false).pairs.len(), 2);
+        let brackets = textarea.brackets(true);
+        assert!(brackets.pairs.is_empty());
+        assert_eq!(brackets.unmatched, [(0, 0)]);
+
+        // A bracket in the input is not unmatched
+        let textarea = TextArea::from(["[-]!", "["]);
+        let palette = Palette::default();
+        let mut term = terminal(8, 2);
+        let widget = Editor::new(&textarea, &palette, Extension::None)
+            .embedded_input(true);
+        let mut state = EditorState::default();
+        term.draw(|f| f.render_stateful_widget(widget, f.area(), &mut state))
+            .unwrap();
+        let buf = term.backend().buffer();
+        assert_ne!(buf[(3, 1)].bg, palette.unmatched.bg.unwrap());
+        assert_eq!(buf[(0, 1)].symbol(), " ");
+    }
+}
@@ -9,6 +9,7 @@ const KEY_BINDINGS: &[(&str, &str)] = &[
     ("^X", "Save As"),
     ("^C", "Quit"),
     ("^A", "Toggle ASCII"),
+    ("^B", "Matching Bracket"),
     ("F1", "Set Input"),
     ("F2", "Set Auto-Input"),
 ];
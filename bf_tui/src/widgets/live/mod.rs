@@ -1,6 +1,7 @@
 bf_utils::barrel_module! {
     pub mod app;
     pub mod dialogs;
+    pub mod editor;
     pub mod header;
     pub mod footer;
     pub mod tape;
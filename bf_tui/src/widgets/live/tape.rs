@@ -31,11 +31,20 @@ impl TapeViewportState {
 
 pub struct TapeViewport<'tape> {
     tape: &'tape Tape,
+    /// The cursor of each thread. Only the cursor of the tape when empty.
+    cursors: &'tape [usize],
 }
 
 impl<'tape> TapeViewport<'tape> {
     pub const fn new(tape: &'tape Tape) -> Self {
-        Self { tape }
+        Self { tape, cursors: &[] }
+    }
+
+    /// Highlight the cursor of each thread. The viewport follows the cursor
+    /// of the tape.
+    pub const fn cursors(mut self, cursors: &'tape [usize]) -> Self {
+        self.cursors = cursors;
+        self
     }
 }
 
@@ -109,11 +118,18 @@ impl StatefulWidget for TapeViewport<'_> {
                 (vp_cursor - cursorbox_end + 1).min(self.tape.len() - vp_end);
         }
 
-        TapeChunkWidget::new(
+        let cursor = [self.tape.cursor()];
+        let cursors = if self.cursors.is_empty() {
+            &cursor
+        } else {
+            self.cursors
+        };
+        TapeChunkWidget::with_cursors(
             self.tape,
             state.offset,
             vp_width,
             state.ascii_values,
+            cursors,
         )
         .render(area, buf);
     }
@@ -123,7 +139,9 @@ impl StatefulWidget for TapeViewport<'_> {
 mod test {
     use test_case::test_case;
 
-    use crate::test_utils::tape_from_script;
+    use crate::test_utils::{
+        tape_from_script, CELL_STYLE_CURSOR, CELL_STYLE_NORMAL,
+    };
 
     use super::*;
 
@@ -203,7 +221,7 @@ mod test {
             len: usize,
         ) -> String {
             let left = if endcaps.left() { left_cap } else { middle_cap };
-            let middle = iter::repeat(middle).take(len).join(middle_cap);
+            let middle = iter::repeat_n(middle, len).join(middle_cap);
             let right = if endcaps.right() { right_cap } else { middle_cap };
             String::with_capacity(len * 4 + 1) + left + &middle + right
         }
@@ -425,4 +443,24 @@ mod test {
         assert_eq!(state.offset, 0);
         term.backend().assert_buffer(&expected_buf);
     }
+
+    #[test]
+    fn highlights_each_cursor() {
+        let mut term = terminal_for_tape(4);
+
+        let tape = tape_from_script(">>>");
+        let widget = TapeViewport::new(&tape).cursors(&[3, 1]);
+        let mut state = TapeViewportState::default();
+
+        let mut expected_buf =
+            buf_for_tape_viewport(TapeEndcaps::LeftRight, 3, &[0, 0, 0, 0]);
+        expected_buf.set_tape_content([
+            (0, CELL_STYLE_NORMAL),
+            (0, CELL_STYLE_CURSOR),
+        ]);
+
+        render_tape(&mut term, widget, &mut state);
+
+        term.backend().assert_buffer(&expected_buf);
+    }
 }
@@ -1,3 +1,4 @@
+use bf::interpreter::Brackets;
 use bf_utils::hash::Sha1Digest;
 use crossterm::event::{KeyCode, KeyEvent};
 use sha1::{Digest, Sha1};
@@ -6,8 +7,20 @@ use tui_textarea::{CursorMove, TextArea};
 use crate::events::KeyEventExt;
 
 pub trait TextAreaExts {
+    /// The bytes of the script, as it would be saved.
     fn bytes(&self) -> impl Iterator<Item = u8>;
 
+    /// The line and character of the first `!`, after which the input of
+    /// the script is embedded.
+    fn embedded_input_start(&self) -> Option<(usize, usize)>;
+
+    /// The loop brackets of the text, by line and character, up to the
+    /// first `!` if `embedded_input`.
+    fn brackets(&self, embedded_input: bool) -> Brackets<(usize, usize)>;
+
+    /// Move the cursor from a bracket to its partner.
+    fn jump_to_matching_bracket(&mut self, embedded_input: bool);
+
     fn hash(&self) -> Sha1Digest;
 
     fn is_empty(&self) -> bool;
@@ -21,7 +34,34 @@ pub trait TextAreaExts {
 
 impl TextAreaExts for TextArea<'_> {
     fn bytes(&self) -> impl Iterator<Item = u8> {
-        self.lines().iter().flat_map(|l| l.bytes())
+        // Each line ends with a newline, as when the script is saved
+        self.lines().iter().flat_map(|l| l.bytes().chain(*b"\n"))
+    }
+
+    fn embedded_input_start(&self) -> Option<(usize, usize)> {
+        self.lines().iter().enumerate().find_map(|(row, line)| {
+            line.chars().position(|c| c == '!').map(|col| (row, col))
+        })
+    }
+
+    fn brackets(&self, embedded_input: bool) -> Brackets<(usize, usize)> {
+        let code = self.lines().iter().enumerate().flat_map(|(row, line)| {
+            line.chars().enumerate().map(move |(col, c)| {
+                ((row, col), if c.is_ascii() { c as u8 } else { 0 })
+            })
+        });
+        let code = code.take_while(|(_, c)| !embedded_input || *c != b'!');
+        Brackets::resolve(code, b'[', b']')
+    }
+
+    fn jump_to_matching_bracket(&mut self, embedded_input: bool) {
+        let brackets = self.brackets(embedded_input);
+        if let Some((row, col)) = brackets.partner(self.cursor()) {
+            let (Ok(row), Ok(col)) = (row.try_into(), col.try_into()) else {
+                return;
+            };
+            self.move_cursor(CursorMove::Jump(row, col));
+        }
     }
 
     fn hash(&self) -> Sha1Digest {
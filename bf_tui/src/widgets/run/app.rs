@@ -1,49 +1,235 @@
 use bf::interpreter::Interpreter;
 use ratatui::{
     prelude::{Buffer, Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
     widgets::{Paragraph, StatefulWidget, Widget},
 };
 
-use crate::{sublayouts, widgets::ChunkedTapeWidget};
+use crate::{
+    async_interpreter::Status,
+    widgets::{ChunkedTapeState, ChunkedTapeWidget},
+};
 
 #[derive(Default)]
 pub struct AppWidgetState {
     pub height: u16,
+    pub tape: ChunkedTapeState,
+}
+
+/// The state of the step-by-step playback, shown in the status line.
+#[derive(Clone, Copy)]
+pub struct PlaybackStatus {
+    pub steps: u64,
+    pub paused: bool,
+    pub delay_ms: u64,
+    /// Whether `b` steps back, which it doesn't while tracing.
+    pub can_step_back: bool,
 }
 
-pub struct AppWidget<'interpreter> {
+const PLAYBACK_KEYS: &[(&str, &str)] = &[
+    ("Space", "Pause"),
+    ("n", "Step"),
+    ("b", "Back"),
+    ("c", "To Output"),
+    ("+/-", "Speed"),
+];
+
+pub struct AppWidget<'interpreter, 'input> {
     pub show_tape: bool,
     pub ascii_values: bool,
+    pub width: Option<usize>,
     pub interpreter: &'interpreter Interpreter,
+    pub input_echo: &'input [u8],
+    pub waiting_for_input: bool,
+    pub playback: Option<PlaybackStatus>,
+}
+
+impl AppWidget<'_, '_> {
+    fn status_line(&self) -> Option<Line<'static>> {
+        let playback = self.playback?;
+
+        let instruction = match self.interpreter.peek() {
+            Some(ins) => format!("'{ins}'"),
+            None => String::from("end"),
+        };
+        let state = if playback.paused {
+            Span::styled("Paused", Style::default().bold().fg(Color::Yellow))
+        } else {
+            Span::styled("Running", Style::default().bold().fg(Color::Green))
+        };
+
+        let mut spans = vec![
+            Span::raw(format!(
+                "Step {}  ip {} {instruction}  ",
+                playback.steps,
+                self.interpreter.ip(),
+            )),
+            state,
+            Span::raw(format!("  {}ms/step  ", playback.delay_ms)),
+        ];
+        let keys = PLAYBACK_KEYS
+            .iter()
+            .filter(|(keys, _)| playback.can_step_back || *keys != "b");
+        spans.extend(keys.flat_map(|(keys, desc)| {
+            [
+                keys.bg(Color::Cyan).fg(Color::Black),
+                Span::from(":"),
+                Span::from(*desc),
+                Span::from(" "),
+            ]
+        }));
+        Some(Line::from(spans))
+    }
+
+    fn input_line(&self) -> Option<Line<'static>> {
+        if !self.waiting_for_input && self.input_echo.is_empty() {
+            return None;
+        }
+
+        let echo = String::from_utf8_lossy(self.input_echo).replace('\n', "↵");
+        let mut spans = vec![Span::raw("Input: ").bold(), Span::raw(echo)];
+        if self.waiting_for_input {
+            spans.push(Span::raw("█"));
+            spans.push(Span::raw("  "));
+            spans.push(Span::styled(
+                Status::WaitingForInput.to_string(),
+                Style::default().bold().fg(Color::Yellow),
+            ));
+        }
+        Some(Line::from(spans))
+    }
 }
 
-impl StatefulWidget for AppWidget<'_> {
+impl StatefulWidget for AppWidget<'_, '_> {
     type State = AppWidgetState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let output = self.interpreter.output();
         let output_lines = output.split_terminator('\n').count() as u16;
 
+        let input_line = self.input_line();
+        let input_height = u16::from(input_line.is_some());
+
+        let status_line = self.status_line();
+        let status_height = u16::from(status_line.is_some());
+
+        let tape_width = match self.width {
+            Some(width) => area.width.min(width as u16),
+            None => area.width,
+        };
         let tape_widget = ChunkedTapeWidget::new(
             &self.interpreter.tape,
-            area.width as i32,
+            tape_width as i32,
             self.ascii_values,
         );
-        let tape_height = 3 * tape_widget.len() as u16;
+        // Scroll the tape when it does not fit alongside everything else
+        let tape_height = tape_widget.height().min(
+            area.height
+                .saturating_sub(status_height + output_lines + input_height)
+                .max(5),
+        );
 
-        state.height = tape_height + output_lines;
+        state.height =
+            status_height + tape_height + output_lines + input_height;
 
         let layout = Layout::vertical([
+            Constraint::Length(status_height),
             Constraint::Length(tape_height),
             Constraint::Length(output_lines),
+            Constraint::Length(input_height),
         ])
         .split(area);
-        sublayouts!([tape_area, output_area] = layout);
+        sublayouts!([status_area, tape_area, output_area, input_area] = layout);
+
+        if let Some(line) = status_line {
+            Paragraph::new(line).render(status_area, buf);
+        }
 
-        tape_widget.render(tape_area, buf);
+        let tape_area = Rect {
+            width: tape_width,
+            ..tape_area
+        };
+        tape_widget.render(tape_area, buf, &mut state.tape);
 
         if !output.is_empty() {
             Paragraph::new(output).render(output_area, buf);
         }
+
+        if let Some(line) = input_line {
+            Paragraph::new(line).render(input_area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn widget<'a>(
+        interpreter: &'a Interpreter,
+        input_echo: &'a [u8],
+        waiting_for_input: bool,
+    ) -> AppWidget<'a, 'a> {
+        AppWidget {
+            show_tape: false,
+            ascii_values: false,
+            width: None,
+            interpreter,
+            input_echo,
+            waiting_for_input,
+            playback: None,
+        }
+    }
+
+    #[test]
+    fn no_input_line_when_idle_without_input() {
+        let int = Interpreter::new("".bytes(), [].into(), None);
+        assert!(widget(&int, b"", false).input_line().is_none());
+    }
+
+    #[test]
+    fn input_line_echoes_typed_input() {
+        let int = Interpreter::new("".bytes(), [].into(), None);
+        let line = widget(&int, b"hi\n", false).input_line().unwrap();
+        assert_eq!(line.to_string(), "Input: hi↵");
+    }
+
+    #[test]
+    fn input_line_shows_waiting_indicator() {
+        let int = Interpreter::new("".bytes(), [].into(), None);
+        let line = widget(&int, b"a", true).input_line().unwrap();
+        assert_eq!(line.to_string(), "Input: a█  Waiting for Input…");
+    }
+
+    #[test]
+    fn status_line_shows_step_and_instruction() {
+        let mut int = Interpreter::new("+>-".bytes(), [].into(), None);
+        int.next();
+        let mut widget = widget(&int, b"", false);
+        widget.playback = Some(PlaybackStatus {
+            steps: 1,
+            paused: true,
+            delay_ms: 100,
+            can_step_back: true,
+        });
+        let line = widget.status_line().unwrap().to_string();
+        assert!(line.starts_with("Step 1  ip 1 '>'  Paused  100ms/step"));
+        assert!(line.contains("b:Back"));
+    }
+
+    #[test]
+    fn status_line_hides_step_back_while_tracing() {
+        let int = Interpreter::new("+".bytes(), [].into(), None);
+        let mut widget = widget(&int, b"", false);
+        widget.playback = Some(PlaybackStatus {
+            steps: 0,
+            paused: true,
+            delay_ms: 100,
+            can_step_back: false,
+        });
+        let line = widget.status_line().unwrap().to_string();
+        assert!(!line.contains("b:Back"));
+        assert!(line.contains("n:Step"));
     }
 }
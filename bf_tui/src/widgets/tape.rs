@@ -5,23 +5,33 @@ use itertools::Itertools;
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
-    widgets::Widget,
+    style::Stylize,
+    widgets::{Paragraph, StatefulWidget, Widget},
 };
 
 use crate::widgets::CellWidget;
 
-pub struct ChunkedTapeWidget(Vec<TapeChunkWidget>);
+#[derive(Clone, Copy, Default)]
+pub struct ChunkedTapeState {
+    /// The index of the first visible chunk when the tape is taller than the
+    /// area it is rendered in.
+    pub offset: usize,
+}
+
+pub struct ChunkedTapeWidget {
+    chunks: Vec<TapeChunkWidget>,
+    cursor_chunk: usize,
+}
 
 impl ChunkedTapeWidget {
     pub fn new(tape: &Tape, width: i32, ascii: bool) -> Self {
-        let cells = tape.cells();
-
         // Each cell is 4 wide + the extra vertical separator at the end
-        let chunk_size = ((width - 1) / 4) as usize;
-        let end_tape = cells.len() - 1;
+        let chunk_size = ((width - 1) / 4).max(1) as usize;
+        let end_tape = tape.len() - 1;
+        let bit = tape.is_bits();
 
-        let chunks = cells
-            .iter()
+        let chunks = tape
+            .values()
             .enumerate()
             .chunks(chunk_size)
             .into_iter()
@@ -29,18 +39,19 @@ impl ChunkedTapeWidget {
                 let chunk = chunk.collect::<Vec<_>>();
                 let end_chunk = chunk.len() - 1;
                 chunk.into_iter().enumerate().map(
-                    move |(chunk_i, (tape_i, cell))| {
+                    move |(chunk_i, (tape_i, value))| {
                         let right_border_cap = if chunk_i == end_chunk {
                             Some(tape_i == end_tape)
                         } else {
                             None
                         };
                         CellWidget {
-                            value: cell.value(),
+                            value,
                             left_cap: tape_i == 0,
                             right_border_cap,
                             is_highlighted: tape_i == tape.cursor(),
                             ascii,
+                            bit,
                         }
                     },
                 )
@@ -48,28 +59,86 @@ impl ChunkedTapeWidget {
             .map(|chunk| TapeChunkWidget::from(chunk.into_iter()))
             .collect();
 
-        Self(chunks)
+        Self {
+            chunks,
+            cursor_chunk: tape.cursor() / chunk_size,
+        }
+    }
+
+    /// The height needed to show every chunk without scrolling.
+    pub fn height(&self) -> u16 {
+        u16::try_from(3 * self.chunks.len()).unwrap_or(u16::MAX)
+    }
+
+    fn render_chunks(
+        chunks: impl Iterator<Item = TapeChunkWidget>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let chunks = chunks.collect::<Vec<_>>();
+        let layout =
+            Layout::vertical(iter::repeat_n(Constraint::Length(3), chunks.len()))
+                .split(area);
+
+        for (chunk, &chunk_area) in chunks.into_iter().zip(layout.iter()) {
+            chunk.render(chunk_area, buf);
+        }
     }
 }
 
 impl ChunkedTapeWidget {
     delegate::delegate! {
-        to self.0 {
-            pub fn is_empty(&self) -> bool;
-            pub fn len(&self) -> usize;
+        to self.chunks {
+            pub const fn is_empty(&self) -> bool;
+            pub const fn len(&self) -> usize;
         }
     }
 }
 
-impl Widget for ChunkedTapeWidget {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let layout = Layout::vertical(
-            iter::repeat(Constraint::Length(3)).take(self.0.len()),
-        )
+impl StatefulWidget for ChunkedTapeWidget {
+    type State = ChunkedTapeState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        if self.height() <= area.height {
+            state.offset = 0;
+            Self::render_chunks(self.chunks.into_iter(), area, buf);
+            return;
+        }
+
+        // Keep a line above and below the chunks for the scroll indicators
+        let rows = (area.height.saturating_sub(2) / 3).max(1) as usize;
+        let len = self.chunks.len();
+
+        // Scroll as little as possible to keep the cursor in view
+        if self.cursor_chunk < state.offset {
+            state.offset = self.cursor_chunk;
+        } else if self.cursor_chunk >= state.offset + rows {
+            state.offset = self.cursor_chunk + 1 - rows;
+        }
+        state.offset = state.offset.min(len - rows);
+
+        let hidden_above = state.offset;
+        let hidden_below = len - rows - state.offset;
+
+        let layout = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(3 * rows as u16),
+            Constraint::Length(1),
+        ])
         .split(area);
+        sublayouts!([above_area, chunks_area, below_area] = layout);
 
-        for (chunk, &chunk_area) in self.0.into_iter().zip(layout.iter()) {
-            chunk.render(chunk_area, buf);
+        if hidden_above > 0 {
+            Paragraph::new(format!("▲ {hidden_above} more rows above").dim())
+                .render(above_area, buf);
+        }
+
+        let visible = self.chunks.into_iter().skip(state.offset).take(rows);
+        Self::render_chunks(visible, chunks_area, buf);
+
+        if hidden_below > 0 {
+            Paragraph::new(format!("▼ {hidden_below} more rows below").dim())
+                .render(below_area, buf);
         }
     }
 }
@@ -84,20 +153,32 @@ impl<I: Iterator<Item = CellWidget>> From<I> for TapeChunkWidget {
 
 impl TapeChunkWidget {
     pub fn new(tape: &Tape, offset: usize, size: usize, ascii: bool) -> Self {
-        let cells = tape.cells();
-        let end_tape = cells.len() - 1;
+        Self::with_cursors(tape, offset, size, ascii, &[tape.cursor()])
+    }
+
+    /// Highlight each of `cursors` instead of only the cursor of the tape,
+    /// e.g. one for each thread of a Brainfork program.
+    pub fn with_cursors(
+        tape: &Tape,
+        offset: usize,
+        size: usize,
+        ascii: bool,
+        cursors: &[usize],
+    ) -> Self {
+        let end_tape = tape.len() - 1;
         let end_chunk = (offset + size - 1).min(end_tape);
-        let chunk = cells.iter().enumerate().skip(offset).take(size).map(
-            |(i, cell)| CellWidget {
-                value: cell.value(),
+        let chunk = tape.values().enumerate().skip(offset).take(size).map(
+            |(i, value)| CellWidget {
+                value,
                 left_cap: i == 0,
                 right_border_cap: if i == end_chunk {
                     Some(i == end_tape)
                 } else {
                     None
                 },
-                is_highlighted: i == tape.cursor(),
+                is_highlighted: cursors.contains(&i),
                 ascii,
+                bit: tape.is_bits(),
             },
         );
         Self(chunk.collect())
@@ -112,8 +193,7 @@ impl Widget for TapeChunkWidget {
         }
 
         let layout = Layout::horizontal(
-            iter::repeat(Constraint::Length(4))
-                .take(len - 1)
+            iter::repeat_n(Constraint::Length(4), len - 1)
                 .chain(iter::once(Constraint::Fill(1))),
         )
         .split(area);
@@ -123,3 +203,69 @@ impl Widget for TapeChunkWidget {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::{tape_from_script, terminal};
+
+    use super::*;
+
+    /// Render the tape of a script, 2 cells per row, in an area `height` rows
+    /// tall.
+    fn render_chunked(
+        script: &str,
+        height: u16,
+        state: &mut ChunkedTapeState,
+    ) -> Buffer {
+        let tape = tape_from_script(script);
+        let widget = ChunkedTapeWidget::new(&tape, 9, false);
+        let mut term = terminal(9, height);
+        term.draw(|f| f.render_stateful_widget(widget, f.area(), state))
+            .unwrap();
+        term.backend().buffer().clone()
+    }
+
+    fn row(buf: &Buffer, y: u16) -> String {
+        (0..buf.area.width)
+            .map(|x| buf[(x, y)].symbol())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn chunks_by_width() {
+        let tape = tape_from_script(">>>>");
+        let widget = ChunkedTapeWidget::new(&tape, 9, false);
+        assert_eq!(widget.len(), 3);
+        assert_eq!(widget.height(), 9);
+    }
+
+    #[test]
+    fn does_not_scroll_when_the_tape_fits() {
+        let mut state = ChunkedTapeState { offset: 2 };
+        let buf = render_chunked(">>>>>", 9, &mut state);
+        assert_eq!(state.offset, 0);
+        assert_eq!(row(&buf, 0), "┌───┬───┬");
+    }
+
+    #[test]
+    fn scrolls_down_to_keep_the_cursor_in_view() {
+        let mut state = ChunkedTapeState::default();
+        // 5 rows of chunks, 2 of which fit
+        let buf = render_chunked(&">".repeat(9), 8, &mut state);
+        assert_eq!(state.offset, 3);
+        assert_eq!(row(&buf, 0), "▲ 3 more");
+        assert_eq!(row(&buf, 7), "");
+    }
+
+    #[test]
+    fn scrolls_up_to_keep_the_cursor_in_view() {
+        let mut state = ChunkedTapeState { offset: 3 };
+        let script = ">".repeat(9) + &"<".repeat(9);
+        let buf = render_chunked(&script, 8, &mut state);
+        assert_eq!(state.offset, 0);
+        assert_eq!(row(&buf, 0), "");
+        assert_eq!(row(&buf, 7), "▼ 3 more");
+    }
+}
@@ -1,5 +1,5 @@
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc, Mutex,
 };
 
@@ -20,6 +20,23 @@ impl SharedBool {
     }
 }
 
+#[derive(Clone)]
+pub struct SharedU64(Arc<AtomicU64>);
+
+impl SharedU64 {
+    pub fn new(val: u64) -> Self {
+        Self(Arc::new(AtomicU64::new(val)))
+    }
+
+    pub fn load(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn store(&self, val: u64) {
+        self.0.store(val, Ordering::Relaxed);
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct SharedCell<T: Default + Clone>(Arc<Mutex<T>>);
 